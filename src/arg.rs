@@ -0,0 +1,33 @@
+/// An argument that is parsed with access to the state, for values that have to be looked up,
+/// such as the name of a user being resolved to their id
+///
+/// A field is parsed using Arg rather than [`FromStr`](std::str::FromStr) by marking it with
+/// `#[strand(arg)]`, an error being reported like any other argument that couldn't be cast,
+/// along with the error as its reason if it implements [`Display`](std::fmt::Display)
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use roped::Arg;
+///
+/// struct Users {
+///     ids: HashMap<String, u32>,
+/// }
+///
+/// struct UserId(u32);
+///
+/// impl Arg<Users> for UserId {
+///     type Err = String;
+///
+///     fn parse_arg(arg: &str, state: &Users) -> Result<Self, Self::Err> {
+///         match state.ids.get(arg) {
+///             Some(id) => Ok(UserId(*id)),
+///             None => Err(format!("no user named {}", arg)),
+///         }
+///     }
+/// }
+/// ```
+pub trait Arg<State: ?Sized>: Sized {
+    type Err;
+    fn parse_arg(arg: &str, state: &State) -> Result<Self, Self::Err>;
+}
@@ -0,0 +1,89 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use crate::{
+    context::Context,
+    error::Error,
+    input::{ArgList, Input},
+    render::Render,
+    strand::Strand,
+};
+
+/// Runs a Strand once over the arguments of the program, as a command line tool
+///
+/// `args` are the arguments as given by [`std::env::args`], the first being the name of the
+/// program, which is skipped. Each argument is used as it is, without being split on whitespace
+/// or having quotes removed, as the shell has already done so. A Strand taking the trail is given
+/// the remaining arguments as an [`ArgList`] joins them, escaped so that they can be split apart
+/// again
+///
+/// The output is printed to stdout through its [`Render`] implementation, and an error to
+/// stderr, the exit code being given by [`Error::exit_code`]
+///
+/// ```no_run
+/// use std::process::ExitCode;
+///
+/// use roped::{args::run_args, command::Command, Context, EmptyState, Strand};
+///
+/// #[derive(Strand)]
+/// struct Greet {
+///     name: String,
+/// }
+///
+/// impl Command for Greet {
+///     type State = EmptyState;
+///     type Output = String;
+///     type Err = String;
+///
+///     fn action(self, _: &mut EmptyState, _: &mut Context<'_>) -> Result<String, String> {
+///         Ok(format!("Hello, {}!", self.name))
+///     }
+/// }
+///
+/// fn main() -> ExitCode {
+///     run_args::<Greet>(&mut EmptyState, std::env::args())
+/// }
+/// ```
+pub fn run_args<R: Strand>(
+    state: &mut R::State,
+    args: impl IntoIterator<Item = impl AsRef<str>>,
+) -> ExitCode
+where
+    R::Output: Render,
+    R::Err: Display,
+{
+    let mut stdout = io::stdout();
+
+    let result = match run_args_with::<R>(state, args, &mut stdout) {
+        Ok(output) => output.render(&mut stdout).and_then(|_| stdout.flush()),
+        Err(err) => {
+            let _ = stdout.flush();
+            let _ = writeln!(io::stderr(), "{}", err);
+            return ExitCode::from(err.exit_code());
+        }
+    };
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+/// Runs a Strand once over the arguments of the program, writing to the provided output
+///
+/// The arguments are used as they are by [`run_args`], though the output is returned rather than
+/// printed, and anything written to the [`Context`] goes to `output`
+pub fn run_args_with<R: Strand>(
+    state: &mut R::State,
+    args: impl IntoIterator<Item = impl AsRef<str>>,
+    output: &mut impl Write,
+) -> Result<R::Output, Error<R::Err>> {
+    let args = ArgList::new(args.into_iter().skip(1));
+
+    let mut ctx = Context::new(output);
+
+    R::run(state, &mut ctx, Input::from_args(&args), 1)
+}
@@ -0,0 +1,190 @@
+use std::{fmt::Display, future::Future, io};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    console::{Console, ExitReason, Intercepted},
+    context::Context,
+    error::Error,
+    help::Help,
+    input::Input,
+    render::Render,
+};
+
+/// The asynchronous form of a [`Command`](crate::command::Command), for commands that await
+/// work such as database queries or requests instead of blocking the runtime
+///
+/// `action` can be written as an `async fn`
+///
+/// ```
+/// use std::io::Write;
+///
+/// use roped::{asynchronous::AsyncCommand, Context, EmptyState};
+///
+/// struct Ping;
+///
+/// impl AsyncCommand for Ping {
+///     type State = EmptyState;
+///     type Output = ();
+///     type Err = String;
+///
+///     async fn action(self, _state: &mut EmptyState, ctx: &mut Context<'_>) -> Result<(), String> {
+///         tokio::task::yield_now().await;
+///         ctx.write_all(b"pong\n").map_err(|err| err.to_string())
+///     }
+/// }
+/// ```
+pub trait AsyncCommand {
+    type State: ?Sized;
+    type Output;
+    type Err;
+    fn action(
+        self,
+        state: &mut Self::State,
+        ctx: &mut Context<'_>,
+    ) -> impl Future<Output = Result<Self::Output, Self::Err>>;
+}
+
+/// The asynchronous form of a [`Strand`](crate::strand::Strand)
+///
+/// An AsyncStrand is constructed using [`#[derive(Strand)]`](trait@crate::Strand) with
+/// `#[strand(async)]`, structs then implementing [`AsyncCommand`] instead of
+/// [`Command`](crate::command::Command)
+///
+/// The futures hold on to the [`Context`], which writes to a `dyn Write`, so they are not
+/// [`Send`] and should be run on the task that owns the console rather than spawned
+pub trait AsyncStrand {
+    /// The type of the current state.
+    type State: ?Sized;
+    /// The type of value the Strand returns when it succeeds.
+    type Output;
+    /// The type of error the Strand can return.
+    type Err;
+
+    /// The function that will be awaited by the console application to execute the Strand.
+    ///
+    /// It is given the same arguments as [`Strand::run`](crate::strand::Strand::run).
+    fn run(
+        state: &mut Self::State,
+        ctx: &mut Context<'_>,
+        input: Input<'_>,
+        index: usize,
+    ) -> impl Future<Output = Result<Self::Output, Error<Self::Err>>>;
+
+    /// Describes how the Strand is used, so that help can be printed for it
+    fn help() -> Help {
+        Help::Unknown
+    }
+}
+
+impl<'a, R: AsyncStrand> Console<'a, R>
+where
+    R::Output: Render,
+    R::Err: Display,
+{
+    /// Runs the console over the provided input and output until it is told to stop
+    ///
+    /// This is the asynchronous form of [`Console::run_with`], so any [`AsyncBufRead`] can be
+    /// used, such as a socket, the lines of a file, or a `&[u8]` in tests
+    ///
+    /// What a command writes to the [`Context`] is written to `output` once the command has
+    /// finished
+    pub async fn run_async(
+        &self,
+        state: &mut R::State,
+        input: &mut (impl AsyncBufRead + Unpin),
+        output: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<ExitReason, io::Error> {
+        loop {
+            if let Some(reason) = self.run_once_async(state, input, output).await? {
+                return Ok(reason);
+            }
+        }
+    }
+
+    /// Reads and runs a single line from the provided input, writing to the provided output
+    pub async fn run_once_async(
+        &self,
+        state: &mut R::State,
+        input: &mut (impl AsyncBufRead + Unpin),
+        output: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<Option<ExitReason>, io::Error> {
+        // Prints the prompt if one was provided
+        if !self.prompt.is_empty() {
+            output.write_all(self.prompt.as_bytes()).await?;
+            output.flush().await?;
+        }
+
+        // Reads the input until a newline is encountered
+        let mut read_input = String::new();
+        if input.read_line(&mut read_input).await? == 0 {
+            return Ok(Some(ExitReason::Eof));
+        }
+
+        self.run_line_async(state, &read_input, output).await
+    }
+
+    /// Runs a line of input as if it had been read by the console
+    pub async fn run_line_async(
+        &self,
+        state: &mut R::State,
+        line: &str,
+        output: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<Option<ExitReason>, io::Error> {
+        // The output of each command is buffered, as a Context can only write synchronously
+        let mut buffer: Vec<u8> = Vec::new();
+
+        let line = match self.record_line(line, &mut buffer)? {
+            Some(v) => v,
+            None => return flush(&mut buffer, output, None).await,
+        };
+
+        let commands = self.split_commands(&line);
+        let count = commands.len();
+
+        // Loops over each command in the input
+        for (index, command) in commands.into_iter().enumerate() {
+            let mut ctx = Context::new(&mut buffer);
+
+            match self.intercept(&line, command, index, count, R::help, &mut ctx)? {
+                Intercepted::Exit => {
+                    return flush(&mut buffer, output, Some(ExitReason::ExitCommand)).await
+                }
+                Intercepted::Handled => {
+                    flush(&mut buffer, output, None).await?;
+                    continue;
+                }
+                Intercepted::Run => (),
+            }
+
+            // Runs the command, printing its output or the error if it fails
+            let input = Input::with_line(&line, Some(command));
+            match R::run(state, &mut ctx, input, 1).await {
+                Ok(output) => output.render(&mut ctx)?,
+                Err(err) => self.write_error(&err, &line, &mut ctx)?,
+            }
+
+            // Stops running the rest of the line if the command asked to exit
+            if ctx.exit_requested() {
+                return flush(&mut buffer, output, Some(ExitReason::Requested)).await;
+            }
+
+            flush(&mut buffer, output, None).await?;
+        }
+
+        flush(&mut buffer, output, None).await
+    }
+}
+
+/// Writes out and clears what has been buffered, passing `reason` through
+async fn flush(
+    buffer: &mut Vec<u8>,
+    output: &mut (impl AsyncWrite + Unpin),
+    reason: Option<ExitReason>,
+) -> Result<Option<ExitReason>, io::Error> {
+    output.write_all(buffer).await?;
+    output.flush().await?;
+    buffer.clear();
+
+    Ok(reason)
+}
@@ -1,5 +1,13 @@
+use crate::context::Context;
+
 pub trait Command {
     type State: ?Sized;
+    /// The value returned when the command succeeds, `()` if it only has side effects
+    type Output;
     type Err;
-    fn action(self, state: &mut Self::State) -> Result<(), Self::Err>;
+    fn action(
+        self,
+        state: &mut Self::State,
+        ctx: &mut Context<'_>,
+    ) -> Result<Self::Output, Self::Err>;
 }
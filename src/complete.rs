@@ -0,0 +1,290 @@
+use parsr::{parser::trim::Trim, parser_matcher::Matcher};
+
+use crate::{
+    help::{ArgKind, EntryKind, Help},
+    input::{find_unquoted, Input},
+    strand::Strand,
+};
+
+/// A Strand that can suggest completions for a partially entered command
+///
+/// By default completions are found from the [`Help`] of the Strand, suggesting the names and
+/// prefixes of scopes and the flags of commands. Strands constructed using the
+/// [`#[derive(Strand)]`](trait@crate::Strand) macro implement this, while other Strands can
+/// implement it to either use the default or provide their own completions
+///
+/// A derived enum completes the Strands of its variants from their help, so they don't need to
+/// implement this
+pub trait Complete: Strand {
+    /// Suggests values for the argument being entered
+    ///
+    /// `args` are the arguments before it that have already been entered, and `partial` is what
+    /// has been entered of the argument so far. Every suggestion replaces `partial` entirely
+    fn complete(args: &[&str], partial: &str) -> Vec<String> {
+        complete_help(&Self::help(), args, partial)
+    }
+}
+
+/// The completions for a line of input
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Completion {
+    /// The byte index in the line where the argument being completed begins
+    pub start: usize,
+    /// The values that could replace the line from `start` up to the cursor
+    pub candidates: Vec<String>,
+}
+
+/// A way into another Strand from a scope, used to complete through it
+#[derive(Debug, Clone, Copy)]
+pub struct ScopeEntry {
+    pub kind: EntryKind,
+    pub complete: fn(&[&str], &str) -> Vec<String>,
+}
+
+/// Finds the completions for `line`, where `cursor` is the byte index the user is typing at
+///
+/// A cursor past the end of the line is taken to be at the end, and one inside a character to be
+/// at its start
+///
+/// Only the command the cursor is in is looked at, commands being separated by `nl_chars` and
+/// arguments by `ws_chars`
+pub fn complete_line<R: Complete>(
+    line: &str,
+    cursor: usize,
+    ws_chars: &Matcher<str, char>,
+    nl_chars: &Matcher<str, char>,
+) -> Completion {
+    let (start, args, partial) = split_line(line, cursor, ws_chars, nl_chars);
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    Completion {
+        start,
+        candidates: R::complete(&args, &partial),
+    }
+}
+
+/// Splits the command the cursor is in into the arguments that have been entered and the
+/// argument being entered, along with the byte index where the latter begins
+pub(crate) fn split_line(
+    line: &str,
+    cursor: usize,
+    ws_chars: &Matcher<str, char>,
+    nl_chars: &Matcher<str, char>,
+) -> (usize, Vec<String>, String) {
+    // A cursor past the end or inside a character is moved back to the nearest character
+    let mut cursor = cursor.min(line.len());
+    while !line.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+
+    let line = &line[..cursor];
+
+    // Finds the start of the command the cursor is in
+    let mut command_start = 0;
+    while let Some(i) = find_unquoted(&line[command_start..], nl_chars) {
+        let trail = line[command_start + i..].trim_all(nl_chars);
+        command_start = line.len() - trail.len();
+    }
+
+    let command = &line[command_start..];
+
+    let mut input = Input::from_line(command, ws_chars);
+
+    let mut args: Vec<String> = Vec::new();
+    let mut last_start = command.len();
+
+    loop {
+        let start = command.len() - input.as_str().len();
+
+        match input.next_arg() {
+            Ok(Some(arg)) => {
+                args.push(arg.into_owned());
+                last_start = start;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                // An unterminated quote can only be the argument being entered
+                let rest = input.as_str();
+                args.push(rest.trim_start_matches(['"', '\'']).to_string());
+                last_start = start;
+                break;
+            }
+        }
+    }
+
+    // The argument being entered is empty if whitespace follows the last argument
+    let ended = find_unquoted(&command[last_start..], ws_chars).is_some();
+
+    match args.pop() {
+        Some(partial) if !ended => (command_start + last_start, args, partial),
+        Some(arg) => {
+            args.push(arg);
+            (line.len(), args, String::new())
+        }
+        None => (line.len(), args, String::new()),
+    }
+}
+
+/// Completes through the entries of a scope, as a derived enum does
+///
+/// The first argument is matched against the names of the entries, ignoring case if
+/// `case_insensitive`, then their prefixes, and otherwise the input is completed by the `Other`
+/// entry if there is one
+pub fn complete_scope(
+    entries: &[ScopeEntry],
+    case_insensitive: bool,
+    args: &[&str],
+    partial: &str,
+) -> Vec<String> {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.kind, entry.complete))
+        .collect();
+
+    complete_entries(&entries, case_insensitive, args, partial)
+}
+
+fn complete_entries<F>(
+    entries: &[(EntryKind, F)],
+    case_insensitive: bool,
+    args: &[&str],
+    partial: &str,
+) -> Vec<String>
+where
+    F: Fn(&[&str], &str) -> Vec<String>,
+{
+    let (first, rest) = match args.split_first() {
+        Some(v) => v,
+        None => {
+            let mut candidates = Vec::new();
+
+            let starts_with = |name: &str| match case_insensitive {
+                false => name.starts_with(partial),
+                true => name.to_lowercase().starts_with(&partial.to_lowercase()),
+            };
+
+            for (kind, complete) in entries {
+                match *kind {
+                    EntryKind::Name(name) => {
+                        if starts_with(name) {
+                            candidates.push(name.to_string());
+                        }
+                    }
+                    // Aliases are only suggested once they are entered in full
+                    EntryKind::Alias(alias) => {
+                        if kind.is_named(partial, case_insensitive) {
+                            candidates.push(alias.to_string());
+                        }
+                    }
+                    EntryKind::Prefix(prefix) => {
+                        if let Some(trail) = partial.strip_prefix(prefix) {
+                            candidates.extend(
+                                complete(&[], trail)
+                                    .into_iter()
+                                    .map(|candidate| format!("{}{}", prefix, candidate)),
+                            );
+                        } else if prefix.starts_with(partial) {
+                            candidates.push(prefix.to_string());
+                        }
+                    }
+                    EntryKind::Other => candidates.extend(complete(&[], partial)),
+                }
+            }
+
+            return candidates;
+        }
+    };
+
+    if let Some((_, complete)) = entries
+        .iter()
+        .find(|(kind, _)| kind.is_named(first, case_insensitive))
+    {
+        return complete(rest, partial);
+    }
+
+    for (kind, complete) in entries {
+        if let EntryKind::Prefix(prefix) = *kind {
+            if let Some(trail) = first.strip_prefix(prefix) {
+                if trail.is_empty() {
+                    return complete(rest, partial);
+                }
+
+                let mut args = vec![trail];
+                args.extend_from_slice(rest);
+                return complete(&args, partial);
+            }
+        }
+    }
+
+    match entries.iter().find(|(kind, _)| *kind == EntryKind::Other) {
+        Some((_, complete)) => complete(args, partial),
+        None => Vec::new(),
+    }
+}
+
+/// Completes using only the [`Help`] of a Strand, as [`Complete::complete`] does by default
+pub fn complete_help(help: &Help, args: &[&str], partial: &str) -> Vec<String> {
+    match help {
+        Help::Unknown => Vec::new(),
+        Help::Scope {
+            entries,
+            case_insensitive,
+        } => {
+            let entries: Vec<_> = entries
+                .iter()
+                .map(|entry| {
+                    let help = entry.help;
+                    let complete =
+                        move |args: &[&str], partial: &str| complete_help(&help(), args, partial);
+
+                    (entry.kind, complete)
+                })
+                .collect();
+
+            complete_entries(&entries, *case_insensitive, args, partial)
+        }
+        Help::Command(usage) => {
+            // The value of a flag can't be completed
+            if let Some(last) = args.last() {
+                let takes_value = usage.args.iter().any(|arg| match arg.kind {
+                    ArgKind::Flag {
+                        short,
+                        value: Some(_),
+                        ..
+                    } => {
+                        let long = last.strip_prefix("--") == Some(arg.name);
+
+                        // A group of short flags takes a value if its last flag does
+                        let short = short.is_some_and(|short| {
+                            !last.starts_with("--")
+                                && !last.contains('=')
+                                && last
+                                    .strip_prefix('-')
+                                    .is_some_and(|group| group.ends_with(short))
+                        });
+
+                        long || short
+                    }
+                    _ => false,
+                });
+
+                if takes_value {
+                    return Vec::new();
+                }
+            }
+
+            if !partial.starts_with('-') {
+                return Vec::new();
+            }
+
+            usage
+                .args
+                .iter()
+                .filter(|arg| matches!(arg.kind, ArgKind::Flag { .. }))
+                .map(|arg| format!("--{}", arg.name))
+                .filter(|flag| flag.starts_with(partial))
+                .collect()
+        }
+    }
+}
@@ -1,75 +1,539 @@
 use std::{
-    borrow::Borrow,
-    io::{self, Write},
+    borrow::Cow,
+    cell::{Ref, RefCell},
+    fmt::Display,
+    io::{self, BufRead, Write},
+    marker::PhantomData,
 };
 
 use parsr::{
-    parser::{trimmed::Trimmed, Parser},
-    parser_matcher::Matcher,
+    parser::{trim::Trim, trimmed::Trimmed},
+    parser_matcher::{Matcher, MatcherSingle},
 };
 
-use crate::strand::Strand;
+use crate::{
+    complete::{split_line, Complete, Completion},
+    context::Context,
+    error::{Error, InternalError},
+    help::Help,
+    history::History,
+    input::{find_unquoted, Input},
+    render::Render,
+    strand::Strand,
+};
 
-/// Runs a console using the provided Strand
+/// The reason a console stopped reading input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExitReason {
+    /// The input reached the end of the file
+    Eof,
+    /// The exit command was entered
+    ExitCommand,
+    /// A command asked the console to stop through its [`Context`]
+    Requested,
+}
+impl Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitReason::Eof => write!(f, "reached end of input"),
+            ExitReason::ExitCommand => write!(f, "exit command entered"),
+            ExitReason::Requested => write!(f, "exit requested by a command"),
+        }
+    }
+}
+
+/// A console that reads commands and runs them using the provided Strand
+///
+/// The value a command returns is printed through its [`Render`] implementation
 ///
-/// Takes in a mutable reference to a State and various configuration options
+/// By default a console reads from stdin and writes to stdout, though any [`BufRead`] and
+/// [`Write`] can be used instead through [`Console::run_with`]
 ///
-/// `prompt`, `counter_suffix`, and `error_prefix` are all optional and will be used if provided,
-/// otherwise they will be left as default values
+/// A Console is configured by chaining setters onto [`Console::new`], any option that isn't set
+/// being left as its default value
 ///
-/// `ws_chars` and `nl_chars` are used to determine the argument separators and newline separators
-/// for parsing the input into individual commands
-pub fn run_console<'a, R: Strand<Err = String>>(
-    state: &mut R::State,
-    prompt: Option<&str>,
-    counter_suffix: Option<&str>,
-    err_prefix: Option<&str>,
-    ws_chars: impl Borrow<Matcher<'a, str, char>>,
-    nl_chars: impl Borrow<Matcher<'a, str, char>>,
-) -> Result<(), io::Error> {
-    // Prints the prompt if one was provided
-    if let Some(prompt) = prompt {
-        print!("{}", prompt);
-        io::stdout().flush()?;
+/// | option            | default         |
+/// |-------------------|-----------------|
+/// | `prompt`          | `"> "`          |
+/// | `counter_suffix`  | `" "`           |
+/// | `err_prefix`      | `"!"`           |
+/// | `exit_command`    | none            |
+/// | `help_command`    | `"help"`        |
+/// | `diagnostics`     | `false`         |
+/// | `history`         | none            |
+/// | `history_command` | `"history"`     |
+/// | `ws_chars`        | `' '`           |
+/// | `nl_chars`        | `'\n'` or `';'` |
+///
+/// # Examples
+///
+/// ```no_run
+/// use roped::{console::Console, Context, EmptyState, Error, Input, Strand};
+/// # struct Commands;
+/// # impl Strand for Commands {
+/// #     type State = EmptyState;
+/// #     type Output = ();
+/// #     type Err = String;
+/// #     fn run(
+/// #         _: &mut EmptyState,
+/// #         _: &mut Context<'_>,
+/// #         _: Input<'_>,
+/// #         _: usize,
+/// #     ) -> Result<(), Error<String>> {
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// let reason = Console::<Commands>::new()
+///     .prompt(">> ")
+///     .exit_command("exit")
+///     .run(&mut EmptyState)
+///     .unwrap();
+///
+/// println!("console stopped: {}", reason);
+/// ```
+pub struct Console<'a, R> {
+    pub(crate) prompt: String,
+    counter_suffix: String,
+    err_prefix: String,
+    exit_command: Option<String>,
+    help_command: Option<String>,
+    diagnostics: bool,
+    history: Option<RefCell<History>>,
+    history_command: Option<String>,
+    ws_chars: Matcher<'a, str, char>,
+    nl_chars: Matcher<'a, str, char>,
+    strand: PhantomData<fn() -> R>,
+}
+impl<'a, R> Default for Console<'a, R> {
+    fn default() -> Self {
+        Self {
+            prompt: "> ".to_string(),
+            counter_suffix: " ".to_string(),
+            err_prefix: "!".to_string(),
+            exit_command: None,
+            help_command: Some("help".to_string()),
+            diagnostics: false,
+            history: None,
+            history_command: Some("history".to_string()),
+            ws_chars: Matcher::Single(MatcherSingle::Item(' ')),
+            nl_chars: Matcher::List(&[MatcherSingle::Item('\n'), MatcherSingle::Item(';')]),
+            strand: PhantomData,
+        }
+    }
+}
+impl<'a, R> Console<'a, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prompt printed before reading each line, an empty prompt prints nothing
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Sets the suffix printed after the index of each command when a line has several
+    pub fn counter_suffix(mut self, counter_suffix: impl Into<String>) -> Self {
+        self.counter_suffix = counter_suffix.into();
+        self
+    }
+
+    /// Sets the prefix printed before every error
+    pub fn err_prefix(mut self, err_prefix: impl Into<String>) -> Self {
+        self.err_prefix = err_prefix.into();
+        self
+    }
+
+    /// Sets a command that stops the console when it is entered on its own
+    pub fn exit_command(mut self, exit_command: impl Into<String>) -> Self {
+        self.exit_command = Some(exit_command.into());
+        self
+    }
+
+    /// Sets the command that prints the help of the Strand, or of the Strand reached by the
+    /// arguments following it, such as `help flag`
+    pub fn help_command(mut self, help_command: impl Into<String>) -> Self {
+        self.help_command = Some(help_command.into());
+        self
+    }
+
+    /// Removes the help command, leaving the input to the Strand
+    pub fn no_help_command(mut self) -> Self {
+        self.help_command = None;
+        self
+    }
+
+    /// Sets whether the line is printed after an error with carets under the argument that
+    /// caused it, as given by [`InternalError::diagnostic`]
+    pub fn diagnostics(mut self, diagnostics: bool) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Sets the history the console records every line to, enabling `!!` and `!n` to recall
+    /// the last line or the line numbered `n`
+    ///
+    /// If the history was loaded from a file using [`History::load`], it is saved to that file
+    /// after every line
+    pub fn history(mut self, history: History) -> Self {
+        self.history = Some(RefCell::new(history));
+        self
+    }
+
+    /// Sets the command that prints the history, which is only available if there is a history
+    pub fn history_command(mut self, history_command: impl Into<String>) -> Self {
+        self.history_command = Some(history_command.into());
+        self
+    }
+
+    /// Removes the history command, leaving the input to the Strand
+    pub fn no_history_command(mut self) -> Self {
+        self.history_command = None;
+        self
+    }
+
+    /// The lines the console has run, if it was given a history
+    pub fn get_history(&self) -> Option<Ref<'_, History>> {
+        self.history.as_ref().map(RefCell::borrow)
+    }
+
+    /// Takes the history back from the console
+    pub fn into_history(self) -> Option<History> {
+        self.history.map(RefCell::into_inner)
+    }
+
+    /// Sets the characters used to separate the arguments of a command
+    pub fn ws_chars(mut self, ws_chars: Matcher<'a, str, char>) -> Self {
+        self.ws_chars = ws_chars;
+        self
+    }
+
+    /// Sets the characters used to separate the commands on a line
+    pub fn nl_chars(mut self, nl_chars: Matcher<'a, str, char>) -> Self {
+        self.nl_chars = nl_chars;
+        self
+    }
+
+    /// The history command, if there is a history for it to print
+    fn active_history_command(&self) -> Option<&str> {
+        self.history.as_ref().and(self.history_command.as_deref())
+    }
+}
+impl<'a, R: Complete> Console<'a, R> {
+    /// Finds the completions for `line`, where `cursor` is the byte index the user is typing at
+    ///
+    /// This uses the separators of the console, and completes the help command along with the
+    /// commands of the Strand. The cursor is moved into the line as [`complete_line`] does
+    ///
+    /// [`complete_line`]: crate::complete::complete_line
+    pub fn complete(&self, line: &str, cursor: usize) -> Completion {
+        let (start, args, partial) = split_line(line, cursor, &self.ws_chars, &self.nl_chars);
+
+        let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let mut candidates = Vec::new();
+
+        if let Some(help_command) = self.help_command.as_deref() {
+            if args.is_empty() && help_command.starts_with(partial.as_str()) {
+                candidates.push(help_command.to_string());
+            } else if args.first() == Some(&help_command) {
+                args.remove(0);
+            }
+        }
+
+        if let Some(history_command) = self.active_history_command() {
+            if args.is_empty() && history_command.starts_with(partial.as_str()) {
+                candidates.push(history_command.to_string());
+            }
+        }
+
+        candidates.extend(R::complete(&args, &partial));
+
+        Completion { start, candidates }
     }
+}
+impl<'a, R: Strand> Console<'a, R>
+where
+    R::Output: Render,
+    R::Err: Display,
+{
+    /// Runs the console over stdin and stdout until it is told to stop
+    ///
+    /// Lines are read and run until the end of the input is reached, the exit command is
+    /// entered, or a command calls [`Context::exit`], the reason being returned
+    pub fn run(&self, state: &mut R::State) -> Result<ExitReason, io::Error> {
+        self.run_with(state, &mut io::stdin().lock(), &mut io::stdout())
+    }
+
+    /// Reads and runs a single line from stdin, writing to stdout
+    ///
+    /// Returns the reason to stop if the line caused the console to exit, or `None` if the
+    /// console should keep reading
+    pub fn run_once(&self, state: &mut R::State) -> Result<Option<ExitReason>, io::Error> {
+        self.run_once_with(state, &mut io::stdin().lock(), &mut io::stdout())
+    }
+
+    /// Runs the console over the provided input and output until it is told to stop
+    ///
+    /// Everything the console prints, including the prompt, counters, errors and anything
+    /// written to the [`Context`] by a command, is written to `output`
+    pub fn run_with(
+        &self,
+        state: &mut R::State,
+        input: &mut impl BufRead,
+        output: &mut impl Write,
+    ) -> Result<ExitReason, io::Error> {
+        loop {
+            if let Some(reason) = self.run_once_with(state, input, output)? {
+                return Ok(reason);
+            }
+        }
+    }
+
+    /// Reads and runs a single line from the provided input, writing to the provided output
+    pub fn run_once_with(
+        &self,
+        state: &mut R::State,
+        input: &mut impl BufRead,
+        output: &mut impl Write,
+    ) -> Result<Option<ExitReason>, io::Error> {
+        // Prints the prompt if one was provided
+        if !self.prompt.is_empty() {
+            write!(output, "{}", self.prompt)?;
+            output.flush()?;
+        }
+
+        // Reads the input until a newline is encountered
+        let mut read_input = String::new();
+        if input.read_line(&mut read_input)? == 0 {
+            return Ok(Some(ExitReason::Eof));
+        }
+
+        self.run_line(state, &read_input, output)
+    }
+
+    /// Runs a line of input as if it had been read by the console
+    ///
+    /// The line is split into commands using `nl_chars`, each being run in turn, though
+    /// separators inside quotes or escaped with a backslash do not split a command
+    pub fn run_line(
+        &self,
+        state: &mut R::State,
+        line: &str,
+        output: &mut impl Write,
+    ) -> Result<Option<ExitReason>, io::Error> {
+        let line = match self.record_line(line, output)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let commands = self.split_commands(&line);
+        let count = commands.len();
+
+        let mut ctx = Context::new(output);
+
+        // Loops over each command in the input
+        for (index, command) in commands.into_iter().enumerate() {
+            match self.intercept(&line, command, index, count, R::help, &mut ctx)? {
+                Intercepted::Exit => return Ok(Some(ExitReason::ExitCommand)),
+                Intercepted::Handled => continue,
+                Intercepted::Run => (),
+            }
+
+            // Runs the command, printing its output or the error if it fails
+            match R::run(state, &mut ctx, Input::with_line(&line, Some(command)), 1) {
+                Ok(output) => output.render(&mut ctx)?,
+                Err(err) => self.write_error(&err, &line, &mut ctx)?,
+            }
+
+            // Stops running the rest of the line if the command asked to exit
+            if ctx.exit_requested() {
+                return Ok(Some(ExitReason::Requested));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// What the console did with a command before it reached the Strand
+pub(crate) enum Intercepted {
+    /// The exit command was entered
+    Exit,
+    /// The console ran the command itself
+    Handled,
+    /// The command should be run by the Strand
+    Run,
+}
+
+impl<'a, R> Console<'a, R> {
+    /// Trims the line, recalling a line from the history if there is one and recording the line
+    /// that is run
+    ///
+    /// Returns `None` if the recall failed, in which case the error has been written to `output`
+    pub(crate) fn record_line<'l>(
+        &self,
+        line: &'l str,
+        output: &mut impl Write,
+    ) -> Result<Option<Cow<'l, str>>, io::Error> {
+        // Trims the trailing whitespace from the input
+        let line: &str = line.trim_end_matches(['\n', '\r']);
+
+        let history = match &self.history {
+            Some(v) => v,
+            None => return Ok(Some(Cow::Borrowed(line))),
+        };
 
-    // Reads the input from stdin until a newline is encountered
-    let mut read_input = String::new();
-    io::stdin().read_line(&mut read_input)?;
+        let expanded = match history.borrow().expand(line) {
+            Ok(v) => v.into_owned(),
+            Err(err) => {
+                writeln!(output, "{}{}", self.err_prefix, err)?;
+                return Ok(None);
+            }
+        };
 
-    // Sets the `counter_suffix` and `error_prefix` to a default value if one wasn't provided
-    let counter_suffix = counter_suffix.unwrap_or(" ");
-    let err_prefix = err_prefix.unwrap_or("!");
+        // Shows the recalled line as it is about to be run
+        if expanded != line {
+            writeln!(output, "{}", expanded)?;
+        }
+
+        let mut history = history.borrow_mut();
+        history.push(expanded.as_str());
+        history.save()?;
+
+        Ok(Some(Cow::Owned(expanded)))
+    }
+
+    /// Splits the line into the commands the console runs
+    pub(crate) fn split_commands<'l>(&'l self, line: &'l str) -> Vec<Trimmed<'l, str>> {
+        split_commands(line, &self.ws_chars, &self.nl_chars)
+    }
+
+    /// Deals with the exit, help and history commands, and prints the index of the command if
+    /// there is more than one on the line
+    pub(crate) fn intercept(
+        &self,
+        line: &str,
+        command: Trimmed<str>,
+        index: usize,
+        count: usize,
+        help: fn() -> Help,
+        ctx: &mut Context<'_>,
+    ) -> Result<Intercepted, io::Error> {
+        // Stops before running anything else if the exit command was entered
+        if self.exit_command.as_deref() == Some(command.get_internal()) {
+            return Ok(Intercepted::Exit);
+        }
+
+        // Prints the index if there is more than one command, once the command writes something
+        if count > 1 {
+            ctx.prefix_next_write(format!("{}{}", index + 1, self.counter_suffix));
+        }
 
-    // Trims the trailing whitespace from the input
-    let input: &str = read_input.trim_end_matches(['\n', '\r']);
+        let mut input = Input::with_line(line, Some(command));
 
-    // Creates an iterator over the input, separated by newline characters
-    let mut iter = input.parse_all(nl_chars.borrow());
+        // Prints the help instead of running the command if the help command was entered
+        if let Some(help_command) = &self.help_command {
+            if input.next_arg().ok().flatten().as_deref() == Some(help_command.as_str()) {
+                self.write_help(help(), input, ctx)?;
+                return Ok(Intercepted::Handled);
+            }
+        }
 
-    // Index to keep track of which command is being run
-    let mut index = 1usize;
+        // Prints the history instead of running the command if the history command was
+        // entered on its own
+        if let (Some(history_command), Some(history)) =
+            (self.active_history_command(), &self.history)
+        {
+            if command.get_internal() == history_command {
+                history.borrow().write(ctx)?;
+                return Ok(Intercepted::Handled);
+            }
+        }
+
+        Ok(Intercepted::Run)
+    }
 
-    // Loops over each command in the input
-    while let Some(command) = iter.next() {
-        // Trims whitespace from the command
-        let command = Trimmed::<str>::new(command.get_internal(), ws_chars.borrow());
+    /// Writes the error of a command, followed by its diagnostic if they are enabled
+    pub(crate) fn write_error<Err: Display>(
+        &self,
+        err: &Error<Err>,
+        line: &str,
+        out: &mut dyn Write,
+    ) -> Result<(), io::Error> {
+        writeln!(out, "{}{}", self.err_prefix, err)?;
 
-        if command.is_none() {
-            continue;
+        match err {
+            Error::Internal(err) => self.write_diagnostic(err, line, out),
+            Error::Err(_) => Ok(()),
         }
+    }
+
+    fn write_diagnostic(
+        &self,
+        err: &InternalError,
+        line: &str,
+        out: &mut dyn Write,
+    ) -> Result<(), io::Error> {
+        match err.diagnostic(line) {
+            Some(diagnostic) if self.diagnostics => writeln!(out, "{}", diagnostic),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_help(
+        &self,
+        help: Help,
+        mut input: Input,
+        out: &mut dyn Write,
+    ) -> Result<(), io::Error> {
+        let mut path = Vec::new();
+        loop {
+            match input.next_arg() {
+                Ok(Some(arg)) => path.push(arg),
+                Ok(None) => break,
+                Err(variant) => {
+                    let err = InternalError::new(1, variant).with_span(input.rest_span());
+                    writeln!(out, "{}{}", self.err_prefix, err)?;
+                    return self.write_diagnostic(&err, input.line(), out);
+                }
+            }
+        }
+
+        let path_str = path.join(" ");
+
+        match help.find(&path) {
+            Some(help) => help.write(&path_str, out),
+            None => writeln!(out, "{}No help found for \"{}\"", self.err_prefix, path_str),
+        }
+    }
+}
+
+/// Splits the line into commands separated by `nl_chars`, ignoring any that are quoted or
+/// escaped, and trims the whitespace from each
+pub(crate) fn split_commands<'l>(
+    line: &'l str,
+    ws_chars: &'l Matcher<'l, str, char>,
+    nl_chars: &'l Matcher<'l, str, char>,
+) -> Vec<Trimmed<'l, str>> {
+    let mut input: &str = line;
+
+    let mut commands: Vec<Trimmed<str>> = Vec::new();
+    loop {
+        let (command, trail) = match find_unquoted(input, nl_chars) {
+            Some(i) => (&input[..i], Some(&input[i..])),
+            None => (input, None),
+        };
 
-        // Prints the index if it's not the first command or there are more commands
-        if iter.internal.is_some() || index != 1 {
-            print!("{}{}", index, counter_suffix);
-            index += 1;
+        if let Some(command) = Trimmed::<str>::new(command, ws_chars) {
+            commands.push(command);
         }
 
-        // Runs the command and prints the error if it fails
-        if let Err(err) = R::run(state, command, 1) {
-            println!("{}{}", err_prefix, err);
+        match trail.map(|trail| trail.trim_all(nl_chars)) {
+            Some(trail) => input = trail,
+            None => break,
         }
     }
 
-    Ok(())
+    commands
 }
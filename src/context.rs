@@ -0,0 +1,61 @@
+use std::io::{self, Write};
+
+/// Information shared between the console and the Strands it runs
+///
+/// A Context is handed to every [`Strand`](crate::strand::Strand) alongside the state, and from
+/// there to every [`Command`](crate::command::Command), so that a command can talk back to the
+/// console that is running it
+///
+/// A Context implements [`Write`], writing to the same output as the console, so commands should
+/// prefer `writeln!(ctx, ..)` over `println!(..)`
+pub struct Context<'a> {
+    out: &'a mut dyn Write,
+    exit: bool,
+    /// Written before anything else is, such as the index of a command
+    prefix: Option<String>,
+}
+impl<'a> Context<'a> {
+    pub fn new(out: &'a mut dyn Write) -> Self {
+        Self {
+            out,
+            exit: false,
+            prefix: None,
+        }
+    }
+
+    /// Writes `prefix` before the next thing written, replacing any prefix that is still waiting,
+    /// so that nothing is written for a command that writes nothing
+    pub(crate) fn prefix_next_write(&mut self, prefix: String) {
+        self.prefix = Some(prefix);
+    }
+
+    /// The output the console is writing to
+    pub fn out(&mut self) -> &mut dyn Write {
+        self.out
+    }
+
+    /// Asks the console to stop once the current command has finished
+    pub fn exit(&mut self) {
+        self.exit = true;
+    }
+
+    /// Whether a command has asked the console to stop
+    pub fn exit_requested(&self) -> bool {
+        self.exit
+    }
+}
+impl Write for Context<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            if let Some(prefix) = self.prefix.take() {
+                self.out.write_all(prefix.as_bytes())?;
+            }
+        }
+
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -1,7 +1,8 @@
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 
+#[derive(Debug)]
 pub enum Error<Err> {
-    Internal(InternalError),
+    Internal(Box<InternalError>),
     Err(Err),
 }
 impl<Err> Display for Error<Err>
@@ -15,11 +16,139 @@ where
         }
     }
 }
+impl<Err> Error<Err> {
+    /// Maps the error returned by a command with `f`, leaving an internal error as it is
+    ///
+    /// A derived enum uses this to convert the errors of its Strands into its own with `From`
+    pub fn map<E>(self, f: impl FnOnce(Err) -> E) -> Error<E> {
+        match self {
+            Error::Internal(v) => Error::Internal(v),
+            Error::Err(v) => Error::Err(f(v)),
+        }
+    }
 
+    /// The exit code of a program that stopped because of the error
+    ///
+    /// An [`Error::Internal`] means the arguments were wrong, given `2` as is conventional for a
+    /// usage error, while an [`Error::Err`] returned by a command is given `1`
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Internal(_) => 2,
+            Error::Err(_) => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct InternalError {
     pub index: usize,
+    /// The byte range in the line of the argument that caused the error, if it is known
+    pub span: Option<Range<usize>>,
+    /// The field the argument was given for, if there was one
+    pub field: Option<FieldInfo>,
+    /// What might have been meant instead of an unknown scope or flag, closest first
+    pub suggestions: Vec<&'static str>,
     pub variant: ErrorType,
 }
+impl InternalError {
+    pub fn new(index: usize, variant: ErrorType) -> Self {
+        Self {
+            index,
+            span: None,
+            field: None,
+            suggestions: Vec::new(),
+            variant,
+        }
+    }
+
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_field(mut self, name: &'static str, ty: &'static str) -> Self {
+        self.field = Some(FieldInfo { name, ty });
+        self
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<&'static str>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    /// The suggestions as a question to follow the error, such as `, did you mean "status"?`, or
+    /// an empty string if there are none
+    fn did_you_mean(&self) -> String {
+        let quoted: Vec<String> = self
+            .suggestions
+            .iter()
+            .map(|suggestion| format!("\"{}\"", suggestion))
+            .collect();
+
+        match quoted.split_last() {
+            None => String::new(),
+            Some((last, [])) => format!(", did you mean {}?", last),
+            Some((last, rest)) => format!(", did you mean {} or {}?", rest.join(", "), last),
+        }
+    }
+
+    /// A short description of the error, such as `expected usize: invalid digit found in string`
+    pub fn label(&self) -> String {
+        let expected = |reason: &Option<String>| match (&self.field, reason) {
+            (Some(field), Some(reason)) => Some(format!("expected {}: {}", field.ty, reason)),
+            (Some(field), None) => Some(format!("expected {}", field.ty)),
+            (None, _) => None,
+        };
+
+        match &self.variant {
+            ErrorType::Expected(ArgType::Arg) => match &self.field {
+                Some(field) => format!("expected {} for \"{}\"", field.ty, field.name),
+                None => "expected an argument".to_string(),
+            },
+            ErrorType::Expected(ArgType::Scope) => "expected a command".to_string(),
+            ErrorType::Expected(ArgType::Flag) => "expected a flag".to_string(),
+            ErrorType::Parse(parse_err) => match parse_err.parse_type {
+                ArgType::Scope => format!("command does not exist{}", self.did_you_mean()),
+                _ => expected(&parse_err.reason).unwrap_or_else(|| "invalid argument".to_string()),
+            },
+            ErrorType::InvalidFlag(_) => format!("flag does not exist{}", self.did_you_mean()),
+            ErrorType::MissingFlag(flag) => format!("expected flag {}", flag),
+            ErrorType::Flag(flag_err) => match &flag_err.variant {
+                FlagErrType::ExpectedValue => "expected a value".to_string(),
+                FlagErrType::UnexpectedValue => "does not take a value".to_string(),
+                FlagErrType::NotLast => "takes a value so must be last".to_string(),
+                FlagErrType::Repeated => "given more than once".to_string(),
+                FlagErrType::Parse(parse_err) => {
+                    expected(&parse_err.reason).unwrap_or_else(|| "invalid value".to_string())
+                }
+            },
+            ErrorType::UnterminatedQuote(_) => "unterminated quote".to_string(),
+            ErrorType::Unexpected(_) => "unexpected argument".to_string(),
+            ErrorType::Validation(validation_err) => validation_err.rule.to_string(),
+        }
+    }
+
+    /// Renders `line` with carets under the argument that caused the error, followed by its
+    /// [`label`](InternalError::label)
+    ///
+    /// ```text
+    /// flag x
+    ///      ^ expected usize: invalid digit found in string
+    /// ```
+    ///
+    /// Returns `None` if the span of the error isn't known or doesn't fit in `line`
+    pub fn diagnostic(&self, line: &str) -> Option<String> {
+        let span = self.span.clone()?;
+
+        let before = line.get(..span.start)?;
+        let within = line.get(span)?;
+
+        let padding = " ".repeat(before.chars().count());
+        let carets = "^".repeat(within.chars().count().max(1));
+
+        Some(format!("{}\n{}{} {}", line, padding, carets, self.label()))
+    }
+}
 impl Display for InternalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.variant {
@@ -31,18 +160,61 @@ impl Display for InternalError {
             ErrorType::Parse(parse_err) => match parse_err.parse_type {
                 ArgType::Scope => write!(
                     f,
-                    "Scope \"{}\" does not exist ({})",
-                    parse_err.arg, self.index
+                    "Scope \"{}\" does not exist{} ({})",
+                    parse_err.arg,
+                    self.did_you_mean(),
+                    self.index
                 ),
                 ArgType::Arg => write!(
                     f,
                     "Unable to cast argument \"{}\" ({})",
                     parse_err.arg, self.index
                 ),
-                ArgType::Flag => unreachable!(),
+                ArgType::Flag => write!(
+                    f,
+                    "Unable to cast \"{}\" for a flag ({})",
+                    parse_err.arg, self.index
+                ),
+            },
+            ErrorType::InvalidFlag(flag) => write!(
+                f,
+                "Flag \"{}\" does not exist{} ({})",
+                flag,
+                self.did_you_mean(),
+                self.index
+            ),
+            ErrorType::MissingFlag(flag) => {
+                write!(f, "Expected flag \"{}\" ({})", flag, self.index)
+            }
+            ErrorType::Flag(flag_err) => match &flag_err.variant {
+                FlagErrType::ExpectedValue => write!(
+                    f,
+                    "Expected a value for flag \"{}\" ({})",
+                    flag_err.flag, self.index
+                ),
+                FlagErrType::UnexpectedValue => write!(
+                    f,
+                    "Flag \"{}\" does not take a value, given in \"{}\" ({})",
+                    flag_err.flag, flag_err.arg, self.index
+                ),
+                FlagErrType::NotLast => write!(
+                    f,
+                    "Flag \"{}\" takes a value so must be last in \"{}\" ({})",
+                    flag_err.flag, flag_err.arg, self.index
+                ),
+                FlagErrType::Repeated => write!(
+                    f,
+                    "Flag \"{}\" was given more than once ({})",
+                    flag_err.flag, self.index
+                ),
+                FlagErrType::Parse(parse_err) => write!(
+                    f,
+                    "Unable to cast \"{}\" for flag \"{}\" ({})",
+                    parse_err.arg, flag_err.flag, self.index
+                ),
             },
-            ErrorType::InvalidFlag(flag) => {
-                write!(f, "Flag \"--{}\" does not exist ({})", flag, self.index)
+            ErrorType::UnterminatedQuote(arg) => {
+                write!(f, "Unterminated quote in \"{}\" ({})", arg, self.index)
             }
             ErrorType::Unexpected(unexpected) => {
                 write!(
@@ -51,22 +223,210 @@ impl Display for InternalError {
                     unexpected, self.index
                 )
             }
+            ErrorType::Validation(validation_err) => write!(
+                f,
+                "Invalid value \"{}\" for \"{}\", {} ({})",
+                validation_err.arg, validation_err.field, validation_err.rule, self.index
+            ),
         }
     }
 }
 
+#[derive(Debug)]
 pub enum ErrorType {
     Unexpected(String),
     Expected(ArgType),
     Parse(ParseErr),
+    /// A flag that does not exist, as it was written, such as `--name` or `-n`
     InvalidFlag(String),
+    /// A required flag that wasn't given, by its long form, such as `--name`
+    MissingFlag(String),
+    Flag(FlagErr),
+    UnterminatedQuote(String),
+    /// A value that was cast but broke a rule set on its field
+    Validation(ValidationErr),
 }
 
+/// An error with a flag that does exist
+#[derive(Debug)]
+pub struct FlagErr {
+    /// The flag that failed, such as `--name` or `-n`
+    pub flag: String,
+    /// The argument the flag was given in, such as `--name=value` or `-vn`
+    pub arg: String,
+    pub form: FlagForm,
+    pub variant: FlagErrType,
+}
+
+/// The way a flag was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagForm {
+    /// `--name`, followed by its value if it takes one
+    Long,
+    /// `--name=value`
+    LongAssigned,
+    /// `-n`, followed by its value if it takes one
+    Short,
+    /// `-n=value`, or a group of short flags ending with it, such as `-vn=value`
+    ShortAssigned,
+    /// A group of short flags, such as `-vq`
+    Combined,
+}
+
+#[derive(Debug)]
+pub enum FlagErrType {
+    /// The flag takes a value that wasn't given
+    ExpectedValue,
+    /// The flag was given a value it doesn't take
+    UnexpectedValue,
+    /// A short flag that takes a value wasn't the last in its group
+    NotLast,
+    /// A flag that can only be given once was given again
+    Repeated,
+    /// The value of the flag couldn't be cast
+    Parse(ParseErr),
+}
+
+#[derive(Debug)]
 pub struct ParseErr {
     pub arg: String,
     pub parse_type: ArgType,
+    /// Why the argument couldn't be cast, if its error could be displayed
+    pub reason: Option<String>,
+}
+
+/// A value that broke a rule set on its field with `#[strand(..)]`
+#[derive(Debug)]
+pub struct ValidationErr {
+    /// The name of the field the value was given for
+    pub field: &'static str,
+    /// The value as it was entered
+    pub arg: String,
+    pub rule: Rule,
+}
+
+/// A rule that a value must follow, as set on its field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// `range = <range>`, holding the range as it was written
+    Range(&'static str),
+    /// `choices = [..]`, holding each choice as it was written
+    Choices(&'static [&'static str]),
+    /// `min_len = <n>`, the fewest characters the value can have
+    MinLen(usize),
+    /// `validate = <fn>`, holding the reason the function gave
+    Custom(String),
+}
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::Range(range) => write!(f, "must be in {}", range),
+            Rule::Choices(choices) => write!(f, "must be one of {}", choices.join(", ")),
+            Rule::MinLen(min_len) => write!(f, "must be at least {} characters long", min_len),
+            Rule::Custom(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// The name and type of the field an argument was given for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub ty: &'static str,
+}
+
+/// Finds the candidates closest to `arg` by edit distance, ignoring case, to suggest in place of
+/// an unknown scope or flag
+///
+/// Only candidates within a third of the length of `arg` of it, or within one for short
+/// arguments, are returned, along with only those tied for the closest
+///
+/// ```
+/// use roped::error::suggestions;
+///
+/// assert_eq!(suggestions("stauts", &["status", "stash", "log"]), vec!["status"]);
+/// assert!(suggestions("xyz", &["status", "stash", "log"]).is_empty());
+/// ```
+pub fn suggestions(arg: &str, candidates: &[&'static str]) -> Vec<&'static str> {
+    let arg: Vec<char> = arg.to_lowercase().chars().collect();
+    let max = (arg.len() / 3).max(1);
+
+    let mut closest: Vec<&'static str> = Vec::new();
+    let mut closest_distance = max;
+
+    for candidate in candidates {
+        let distance = edit_distance(&arg, &candidate.to_lowercase().chars().collect::<Vec<_>>());
+
+        if distance < closest_distance {
+            closest.clear();
+            closest_distance = distance;
+        }
+
+        if distance <= closest_distance && !closest.contains(candidate) {
+            closest.push(candidate);
+        }
+    }
+
+    closest
+}
+
+/// The edit distance between `a` and `b`, counting insertions, deletions, substitutions and
+/// swapping two neighbouring characters as a single edit each
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// Finds the reason a value couldn't be parsed, used by derived Strands
+///
+/// `(&Reason(&err)).reason()` gives the error as a string if it implements [`Display`], and
+/// `None` otherwise, as long as both [`DisplayReason`] and [`NoReason`] are in scope
+#[doc(hidden)]
+pub struct Reason<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait DisplayReason {
+    fn reason(&self) -> Option<String>;
+}
+impl<T: Display> DisplayReason for Reason<'_, T> {
+    fn reason(&self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+}
+
+#[doc(hidden)]
+pub trait NoReason {
+    fn reason(&self) -> Option<String>;
+}
+impl<T> NoReason for &Reason<'_, T> {
+    fn reason(&self) -> Option<String> {
+        None
+    }
 }
 
+#[derive(Debug)]
 pub enum ArgType {
     Scope,
     Arg,
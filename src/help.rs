@@ -0,0 +1,257 @@
+use std::io::{self, Write};
+
+/// Describes how a Strand is used, so that help can be printed for it
+///
+/// Help is generated by [`#[derive(Strand)]`](trait@crate::Strand), doc comments on the fields
+/// and variants being used as their descriptions
+#[derive(Debug, Clone, Default)]
+pub enum Help {
+    /// Nothing is known about how the Strand is used
+    #[default]
+    Unknown,
+    /// A Strand that runs one of several other Strands
+    Scope {
+        entries: Vec<Entry>,
+        /// Whether names and aliases are matched regardless of their case
+        case_insensitive: bool,
+    },
+    /// A Strand that parses its arguments and runs a command
+    Command(Usage),
+}
+
+/// A way into another Strand from a scope
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub kind: EntryKind,
+    pub description: Option<&'static str>,
+    pub help: fn() -> Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Reached when the first argument is the name
+    Name(&'static str),
+    /// Reached when the first argument is the alias, another name for the entry of the name it
+    /// follows, which is left out when the scope is listed
+    Alias(&'static str),
+    /// Reached when the input begins with the prefix
+    Prefix(&'static str),
+    /// Reached when nothing else matches
+    Other,
+}
+
+/// The arguments a command takes
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    pub description: Option<&'static str>,
+    pub args: Vec<Arg>,
+}
+
+/// A single argument of a command
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub description: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// An argument that must be given
+    Positional,
+    /// An argument that may be left out, holding the default value as it was written
+    Default(&'static str),
+    /// A flag, along with its short form if it has one
+    Flag {
+        short: Option<char>,
+        /// The name of its value if it takes one
+        value: Option<&'static str>,
+        /// Whether the flag must be given
+        required: bool,
+        /// The value used when the flag isn't given, as it was written
+        default: Option<&'static str>,
+    },
+    /// Any number of arguments collected together, between `min` and `max` of them
+    Many { min: usize, max: Option<usize> },
+    /// The rest of the input
+    Trail,
+}
+
+impl Help {
+    /// Follows `path` through the scopes of this Help, returning the Help at the end of it
+    ///
+    /// Each element of the path is matched against the names of the entries of a scope, ignoring
+    /// case if the scope does, then against their prefixes, returning `None` if nothing matches
+    pub fn find<S: AsRef<str>>(self, path: &[S]) -> Option<Help> {
+        let (first, rest) = match path.split_first() {
+            Some(v) => v,
+            None => return Some(self),
+        };
+
+        let (entries, case_insensitive) = match self {
+            Help::Scope {
+                entries,
+                case_insensitive,
+            } => (entries, case_insensitive),
+            _ => return None,
+        };
+
+        let first = first.as_ref();
+
+        let entry = entries
+            .iter()
+            .find(|entry| entry.kind.is_named(first, case_insensitive))
+            .or_else(|| {
+                entries.iter().find(
+                    |entry| matches!(entry.kind, EntryKind::Prefix(p) if first.starts_with(p)),
+                )
+            })?;
+
+        (entry.help)().find(rest)
+    }
+
+    /// The arguments of the Strand in a single line, such as `<num> [--flag]`
+    pub fn usage(&self) -> String {
+        match self {
+            Help::Unknown => "...".to_string(),
+            Help::Scope { .. } => "<command>".to_string(),
+            Help::Command(usage) => usage.line(),
+        }
+    }
+
+    /// Writes the help to `out`, `path` being the input used to reach the Strand
+    pub fn write(&self, path: &str, out: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Help::Unknown => writeln!(out, "No help available for \"{}\"", path),
+            Help::Scope { entries, .. } => {
+                let lines: Vec<(String, Option<&str>)> = entries
+                    .iter()
+                    .filter_map(|entry| {
+                        let usage = (entry.help)().usage();
+
+                        let line = match entry.kind {
+                            EntryKind::Name(name) => join(name, &usage),
+                            EntryKind::Alias(_) => return None,
+                            EntryKind::Prefix(prefix) => format!("{}{}", prefix, usage),
+                            EntryKind::Other => usage,
+                        };
+
+                        Some((line, entry.description))
+                    })
+                    .collect();
+
+                if !path.is_empty() {
+                    writeln!(out, "usage: {} <command>", path)?;
+                }
+
+                writeln!(out, "commands:")?;
+                write_aligned(&lines, out)
+            }
+            Help::Command(usage) => {
+                writeln!(out, "usage: {}", join(path, &usage.line()))?;
+
+                if let Some(description) = usage.description {
+                    writeln!(out, "{}", description)?;
+                }
+
+                let lines: Vec<(String, Option<&str>)> = usage
+                    .args
+                    .iter()
+                    .filter(|arg| arg.description.is_some())
+                    .map(|arg| (arg.term(), arg.description))
+                    .collect();
+
+                write_aligned(&lines, out)
+            }
+        }
+    }
+}
+
+impl EntryKind {
+    /// Whether `arg` is the name or alias of the entry, ignoring case if `case_insensitive`
+    pub fn is_named(&self, arg: &str, case_insensitive: bool) -> bool {
+        match *self {
+            EntryKind::Name(name) | EntryKind::Alias(name) => match case_insensitive {
+                false => name == arg,
+                true => name.to_lowercase() == arg.to_lowercase(),
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Usage {
+    /// The arguments in a single line, such as `<num> [--flag]`
+    pub fn line(&self) -> String {
+        let args: Vec<String> = self.args.iter().map(Arg::usage).collect();
+        args.join(" ")
+    }
+}
+
+impl Arg {
+    /// How the argument appears in a usage line, such as `<num>` or `[--flag <value>]`
+    pub fn usage(&self) -> String {
+        match self.kind {
+            ArgKind::Positional => format!("<{}>", self.name),
+            ArgKind::Default(default) => format!("[{} = {}]", self.name, default),
+            ArgKind::Many { min: 0, .. } => format!("[{}]", self.term()),
+            ArgKind::Many { .. } => self.term(),
+            ArgKind::Flag { required: true, .. } => self.term(),
+            ArgKind::Flag {
+                default: Some(default),
+                ..
+            } => format!("[{} = {}]", self.term(), default),
+            ArgKind::Flag { .. } | ArgKind::Trail => format!("[{}]", self.term()),
+        }
+    }
+
+    fn term(&self) -> String {
+        match self.kind {
+            ArgKind::Positional => format!("<{}>", self.name),
+            ArgKind::Default(_) => format!("[{}]", self.name),
+            ArgKind::Flag { short, value, .. } => {
+                let mut term = match short {
+                    Some(short) => format!("-{}, --{}", short, self.name),
+                    None => format!("--{}", self.name),
+                };
+
+                if let Some(value) = value {
+                    term.push_str(&format!(" <{}>", value));
+                }
+
+                term
+            }
+            ArgKind::Many { .. } => format!("<{}>...", self.name),
+            ArgKind::Trail => format!("{}...", self.name),
+        }
+    }
+}
+
+fn join(path: &str, usage: &str) -> String {
+    match (path.is_empty(), usage.is_empty()) {
+        (false, false) => format!("{} {}", path, usage),
+        (_, true) => path.to_string(),
+        (true, false) => usage.to_string(),
+    }
+}
+
+fn write_aligned(lines: &[(String, Option<&str>)], out: &mut dyn Write) -> io::Result<()> {
+    // Counts chars rather than bytes so that non-ASCII names stay aligned
+    let width = lines
+        .iter()
+        .map(|(line, _)| line.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (line, description) in lines {
+        match description {
+            Some(description) => {
+                let padding = width - line.chars().count();
+                writeln!(out, "  {}{:padding$}  {}", line, "", description)?
+            }
+            None => writeln!(out, "  {}", line)?,
+        }
+    }
+
+    Ok(())
+}
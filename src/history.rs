@@ -0,0 +1,172 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The lines that have been run by a console, oldest first
+///
+/// Only the most recent `capacity` lines are kept, though every line keeps the number it was
+/// given when it was added, so that `!n` always recalls the same line
+///
+/// A History loaded from a file with [`History::load`] remembers the file, and a console using
+/// it saves to the file after every line, so that the history is kept across restarts
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: VecDeque<String>,
+    capacity: usize,
+    /// The number of lines that have been dropped from the front
+    dropped: usize,
+    path: Option<PathBuf>,
+}
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            dropped: 0,
+            path: None,
+        }
+    }
+
+    /// Loads the history from `path`, keeping the most recent `capacity` lines
+    ///
+    /// The History remembers `path`, starting empty if the file doesn't exist yet
+    pub fn load(path: impl Into<PathBuf>, capacity: usize) -> io::Result<Self> {
+        let path = path.into();
+
+        let mut history = Self::new(capacity);
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().for_each(|line| history.push(line)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
+        }
+
+        history.path = Some(path);
+
+        Ok(history)
+    }
+
+    /// Saves the history to the file it was loaded from, doing nothing if there isn't one
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => self.save_to(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Saves the history to `path`, one line per entry
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+
+        for line in &self.entries {
+            writeln!(file, "{}", line)?;
+        }
+
+        file.flush()
+    }
+
+    /// The file the history was loaded from
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a line, dropping the oldest if the history is full
+    ///
+    /// Empty lines are ignored
+    pub fn push(&mut self, line: impl Into<String>) {
+        let line = line.into();
+
+        if line.trim().is_empty() || self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+
+        self.entries.push_back(line);
+    }
+
+    /// The line with the number `n`, counting from 1
+    pub fn get(&self, n: usize) -> Option<&str> {
+        let i = n.checked_sub(self.dropped + 1)?;
+        self.entries.get(i).map(String::as_str)
+    }
+
+    /// The most recent line
+    pub fn last(&self) -> Option<&str> {
+        self.entries.back().map(String::as_str)
+    }
+
+    /// The lines along with their numbers, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, line)| (self.dropped + i + 1, line.as_str()))
+    }
+
+    /// Replaces `line` with the line it recalls if it is `!!` or `!n`, otherwise returning it
+    /// unchanged
+    ///
+    /// `!!` recalls the most recent line and `!n` the line with the number `n`
+    pub fn expand<'a>(&self, line: &'a str) -> Result<Cow<'a, str>, RecallError> {
+        let recall = line.trim();
+
+        let found = match recall.strip_prefix('!') {
+            Some("!") => self.last(),
+            Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                n.parse().ok().and_then(|n| self.get(n))
+            }
+            _ => return Ok(Cow::Borrowed(line)),
+        };
+
+        match found {
+            Some(found) => Ok(Cow::Owned(found.to_string())),
+            None => Err(RecallError(recall.to_string())),
+        }
+    }
+
+    /// Writes the lines along with their numbers to `out`
+    pub fn write(&self, out: &mut dyn Write) -> io::Result<()> {
+        let width = (self.dropped + self.entries.len()).to_string().len();
+
+        for (n, line) in self.iter() {
+            writeln!(out, "{:>width$}  {}", n, line)?;
+        }
+
+        Ok(())
+    }
+}
+impl Default for History {
+    fn default() -> Self {
+        Self::new(500)
+    }
+}
+
+/// A recall such as `!5` for a line that isn't in the history, holding the recall as written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecallError(pub String);
+impl Display for RecallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No history entry for \"{}\"", self.0)
+    }
+}
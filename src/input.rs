@@ -0,0 +1,313 @@
+use std::{borrow::Cow, ops::Range};
+
+use parsr::{
+    parser::{trim::Trim, trimmed::Trimmed},
+    parser_matcher::{Matcher, MatcherSingle},
+};
+
+use crate::error::ErrorType;
+
+/// An argument along with the byte range in the line of the argument as it was entered
+pub type SpannedArg<'a> = (Cow<'a, str>, Range<usize>);
+
+/// The remaining input of a command that is being run by a Strand
+///
+/// Arguments are split on the whitespace matcher of the console, though whitespace inside single
+/// or double quotes, or escaped with a backslash, does not end an argument
+///
+/// | input            | argument     |
+/// |------------------|--------------|
+/// | `file`           | `file`       |
+/// | `"my file"`      | `my file`    |
+/// | `'my file'`      | `my file`    |
+/// | `my\ file`       | `my file`    |
+/// | `"say \"hi\""`   | `say "hi"`   |
+/// | `'C:\dir'`       | `C:\dir`     |
+/// | `a"b c"d`        | `ab cd`      |
+///
+/// Backslashes escape the next character outside of quotes and inside double quotes, while
+/// everything inside single quotes is taken literally
+///
+/// An Input remembers the line it was taken from, so that the span of each argument can be
+/// given as a byte range in that line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Input<'a> {
+    line: &'a str,
+    end: usize,
+    rest: Option<Trimmed<'a, str>>,
+}
+
+/// Arguments that have already been split, such as those given to a program
+///
+/// The arguments are joined by spaces into a line, with any whitespace, quotes and backslashes in
+/// them escaped by a backslash, so that an [`Input`] made from them splits the line back into the
+/// same arguments. The rest of such an Input, as given to a trail, keeps the arguments apart in
+/// the same way
+#[derive(Debug, Clone, Default)]
+pub struct ArgList {
+    line: String,
+}
+impl ArgList {
+    pub fn new(args: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let mut line = String::new();
+
+        for (i, arg) in args.into_iter().enumerate() {
+            let arg = arg.as_ref();
+
+            if i > 0 {
+                line.push(' ');
+            }
+
+            if arg.is_empty() {
+                line.push_str("\"\"");
+                continue;
+            }
+
+            for c in arg.chars() {
+                if c.is_whitespace() || matches!(c, '"' | '\'' | '\\') {
+                    line.push('\\');
+                }
+                line.push(c);
+            }
+        }
+
+        Self { line }
+    }
+
+    /// The arguments joined into a single line
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+}
+
+/// Separates the arguments in the line of an [`ArgList`]
+static ARG_LIST_WS: Matcher<'static, str, char> = Matcher::Single(MatcherSingle::Item(' '));
+impl<'a> Input<'a> {
+    pub fn new(input: Option<Trimmed<'a, str>>) -> Self {
+        let line = input.map_or("", |input| input.get_internal());
+
+        Self {
+            line,
+            end: line.len(),
+            rest: input,
+        }
+    }
+
+    /// Creates an Input from part of `line`, so that spans are given relative to the whole line
+    ///
+    /// If `input` isn't part of `line` then spans are relative to `input` instead
+    pub fn with_line(line: &'a str, input: Option<Trimmed<'a, str>>) -> Self {
+        let rest = match input {
+            Some(v) => v.get_internal(),
+            None => return Self::new(None),
+        };
+
+        let start = (rest.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+
+        if start > line.len() || line.len() - start < rest.len() {
+            return Self::new(input);
+        }
+
+        Self {
+            line,
+            end: start + rest.len(),
+            rest: input,
+        }
+    }
+
+    /// Trims the whitespace from `input` to create an Input
+    pub fn from_line(input: &'a str, ws_chars: &'a Matcher<'a, str, char>) -> Self {
+        Self::new(Trimmed::new(input, ws_chars))
+    }
+
+    /// Creates an Input that takes each of `args` as a whole argument
+    pub fn from_args(args: &'a ArgList) -> Self {
+        Self::from_line(&args.line, &ARG_LIST_WS)
+    }
+
+    /// Whether there is no input remaining
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_none()
+    }
+
+    /// The remaining input exactly as it was entered, without removing any quotes or escapes
+    pub fn as_str(&self) -> &'a str {
+        match self.rest {
+            Some(v) => v.get_internal(),
+            None => "",
+        }
+    }
+
+    /// The line the input was taken from
+    pub fn line(&self) -> &'a str {
+        self.line
+    }
+
+    /// The byte index in the line where the remaining input begins
+    pub fn offset(&self) -> usize {
+        match self.rest {
+            Some(v) => v.get_internal().as_ptr() as usize - self.line.as_ptr() as usize,
+            None => self.end,
+        }
+    }
+
+    /// The byte range in the line of the remaining input
+    pub fn rest_span(&self) -> Range<usize> {
+        self.offset()..self.end
+    }
+
+    /// The remaining input as a [`Trimmed`], for Strands that want to do their own parsing
+    pub fn as_trimmed(&self) -> Option<Trimmed<'a, str>> {
+        self.rest
+    }
+
+    /// Takes the remaining input exactly as it was entered, leaving the Input empty
+    pub fn take_rest(&mut self) -> &'a str {
+        let rest = self.as_str();
+        self.rest = None;
+        rest
+    }
+
+    /// Returns the input following `prefix` if the input begins with it
+    ///
+    /// The prefix is matched against the input as it was entered, before any quotes are removed
+    pub fn strip_prefix(&self, prefix: &str) -> Option<Input<'a>> {
+        let rest = self.rest?;
+
+        let trail = rest.get_internal().trim_once(Matcher::ident(&prefix))?;
+
+        Some(Input {
+            rest: Trimmed::new(trail, rest.get_matcher()),
+            ..*self
+        })
+    }
+
+    /// Splits off the next argument, removing its quotes and escapes
+    ///
+    /// Returns `None` if there is no input remaining, or an [`ErrorType::UnterminatedQuote`] if a
+    /// quote is opened but never closed, in which case the input is left untouched
+    pub fn next_arg(&mut self) -> Result<Option<Cow<'a, str>>, ErrorType> {
+        Ok(self.next_arg_spanned()?.map(|(arg, _)| arg))
+    }
+
+    /// Splits off the next argument as [`Input::next_arg`] does, along with its span
+    pub fn next_arg_spanned(&mut self) -> Result<Option<SpannedArg<'a>>, ErrorType> {
+        let rest = match self.rest {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let start = self.offset();
+
+        let (arg, trail) = split_arg(rest.get_internal(), rest.get_matcher())?;
+
+        let span = start..start + rest.get_internal().len() - trail.len();
+
+        self.rest = Trimmed::new(trail, rest.get_matcher());
+
+        Ok(Some((arg, span)))
+    }
+
+    /// Returns the next argument without removing it from the input
+    pub fn peek_arg(&self) -> Result<Option<Cow<'a, str>>, ErrorType> {
+        let mut input = *self;
+        input.next_arg()
+    }
+}
+
+/// Splits `s` at the first unquoted, unescaped match of `matcher`
+///
+/// The returned position is the byte index of the match, or `None` if there wasn't one
+pub(crate) fn find_unquoted(s: &str, matcher: &Matcher<str, char>) -> Option<usize> {
+    let mut quote: Option<char> = None;
+
+    let mut chars = s.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            Some(_) => (),
+            None => {
+                if s[i..].trim_once(matcher).is_some() {
+                    return Some(i);
+                }
+
+                match c {
+                    '"' | '\'' => quote = Some(c),
+                    '\\' => {
+                        chars.next();
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn split_arg<'a>(
+    s: &'a str,
+    matcher: &Matcher<str, char>,
+) -> Result<(Cow<'a, str>, &'a str), ErrorType> {
+    // Only allocates once a quote or escape is found
+    let mut owned: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    // Start of the section of `s` that has yet to be copied into `owned`
+    let mut last = 0usize;
+    let mut end = s.len();
+
+    let mut chars = s.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some(q) if c == q => {
+                owned.get_or_insert_with(String::new).push_str(&s[last..i]);
+                last = i + c.len_utf8();
+                quote = None;
+            }
+            Some('"') if c == '\\' => {
+                owned.get_or_insert_with(String::new).push_str(&s[last..i]);
+                last = chars.next().map_or(i, |(j, _)| j);
+            }
+            Some(_) => (),
+            None => {
+                if s[i..].trim_once(matcher).is_some() {
+                    end = i;
+                    break;
+                }
+
+                match c {
+                    '"' | '\'' => {
+                        owned.get_or_insert_with(String::new).push_str(&s[last..i]);
+                        last = i + c.len_utf8();
+                        quote = Some(c);
+                    }
+                    '\\' => {
+                        owned.get_or_insert_with(String::new).push_str(&s[last..i]);
+                        last = chars.next().map_or(i, |(j, _)| j);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(ErrorType::UnterminatedQuote(s.to_string()));
+    }
+
+    let arg = match owned {
+        Some(mut owned) => {
+            owned.push_str(&s[last..end]);
+            Cow::Owned(owned)
+        }
+        None => Cow::Borrowed(&s[..end]),
+    };
+
+    Ok((arg, &s[end..]))
+}
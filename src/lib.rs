@@ -1,16 +1,37 @@
+pub mod arg;
+pub mod args;
+#[cfg(feature = "async")]
+pub mod asynchronous;
 pub mod base_types;
 pub mod command;
+pub mod complete;
 pub mod console;
+pub mod context;
 pub mod error;
+pub mod help;
+pub mod history;
+pub mod input;
+pub mod render;
+pub mod script;
 pub mod strand;
 
+#[allow(unused)]
+pub use arg::Arg;
 #[allow(unused)]
 pub use base_types::EmptyState;
 #[allow(unused)]
 pub use base_types::Trigger;
 #[allow(unused)]
+pub use complete::Complete;
+#[allow(unused)]
+pub use context::Context;
+#[allow(unused)]
+pub use input::Input;
+#[allow(unused)]
 pub use parsr::parser_matcher::Matcher;
 #[allow(unused)]
+pub use render::Render;
+#[allow(unused)]
 pub use strand::Strand;
 #[allow(unused)]
 pub use strand_derive::Strand;
@@ -25,12 +46,16 @@ pub extern crate self as roped;
 
 #[cfg(test)]
 mod tests {
+    use std::{borrow::Cow, io::Write};
+
     use self::command::Command;
 
     use super::*;
 
     use base_types::EmptyState;
-    use console::run_console;
+    use complete::complete_line;
+    use console::{Console, ExitReason};
+    use history::History;
     use parsr::{
         parser::trimmed::Trimmed,
         parser_matcher::{Matcher, MatcherSingle},
@@ -42,27 +67,54 @@ mod tests {
     #[allow(unused)]
     use crate as roped;
 
+    fn run<R: Strand<State = EmptyState>>(console: &Console<R>, input: &str) -> (String, ExitReason)
+    where
+        R::Output: Render,
+        R::Err: std::fmt::Display,
+    {
+        let mut output = Vec::new();
+
+        let reason = console
+            .run_with(&mut EmptyState, &mut input.as_bytes(), &mut output)
+            .unwrap();
+
+        (String::from_utf8(output).unwrap(), reason)
+    }
+
+    /// Runs `line` through the Strand, returning the internal error that it fails with
+    fn internal_err<R: Strand>(state: &mut R::State, line: &str) -> Box<error::InternalError> {
+        let ws_chars = Matcher::Single(MatcherSingle::Item(' '));
+        let mut sink = Vec::new();
+        let mut ctx = Context::new(&mut sink);
+
+        match R::run(state, &mut ctx, Input::from_line(line, &ws_chars), 1) {
+            Err(Error::Internal(err)) => err,
+            _ => panic!("expected an internal error for \"{}\"", line),
+        }
+    }
+
     struct ManualImplStrand;
     impl Strand for ManualImplStrand {
         type State = EmptyState;
+        type Output = ();
         type Err = String;
 
         fn run(
             _state: &mut Self::State,
-            input: Option<Trimmed<str>>,
+            ctx: &mut Context<'_>,
+            mut input: Input<'_>,
             _index: usize,
-        ) -> Result<(), error::Error<Self::Err>> {
-            let input = match input {
-                Some(v) => v,
-                None => return Err(error::Error::Err("Recieved no input".to_string())),
+        ) -> Result<Self::Output, error::Error<Self::Err>> {
+            let arg = match input.next_arg() {
+                Ok(Some(v)) => v,
+                _ => return Err(error::Error::Err("Recieved no input".to_string())),
             };
 
-            let pair = input.parse_once();
-
-            match pair.trail {
-                Some(v) => println!("{} + {}", pair.arg.get_internal(), v.get_internal()),
-                None => println!("{}", pair.arg.get_internal()),
+            match input.is_empty() {
+                false => writeln!(ctx, "{} + {}", arg, input.as_str()),
+                true => writeln!(ctx, "{}", arg),
             }
+            .map_err(|err| error::Error::Err(err.to_string()))?;
 
             Ok(())
         }
@@ -70,15 +122,12 @@ mod tests {
 
     #[test]
     fn manual_strand_instance() {
-        run_console::<ManualImplStrand>(
-            &mut EmptyState,
-            "> ".into(),
-            ". ".into(),
-            "!".into(),
-            Matcher::Single(MatcherSingle::Item(' ')),
-            Matcher::List(&[MatcherSingle::Item('\n'), MatcherSingle::Item(';')]),
-        )
-        .unwrap();
+        let console = Console::<ManualImplStrand>::new().counter_suffix(". ");
+
+        let (output, reason) = run(&console, "a b c\nd; e f\n");
+
+        assert_eq!(output, "> a + b c\n> 1. d\n2. e + f\n> ");
+        assert_eq!(reason, ExitReason::Eof);
     }
 
     #[derive(Strand)]
@@ -91,36 +140,43 @@ mod tests {
     impl Command for TrailStrand {
         type State = EmptyState;
 
+        type Output = ();
+
         type Err = String;
 
-        fn action(self, _state: &mut Self::State) -> Result<(), Self::Err> {
+        fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
             let matcher: Matcher<str, char> = Matcher::Single(MatcherSingle::Item(' '));
 
             let trail = Trimmed::<str>::new(&self.trail, &matcher);
 
-            print!("number: {}", self.num);
+            let mut line = format!("number: {}", self.num);
             if let Some(trail) = trail {
                 let pair = trail.parse_once();
-                print!(" + trail args: {}", pair.arg.get_internal());
+                line += &format!(" + trail args: {}", pair.arg.get_internal());
 
                 if let Some(trail) = pair.trail {
                     for arg in trail.parse_all() {
-                        print!(", {}", arg.get_internal());
+                        line += &format!(", {}", arg.get_internal());
                     }
                 }
             }
 
-            println!();
-
-            Ok(())
+            writeln!(ctx, "{}", line).map_err(|err| err.to_string())
         }
     }
 
+    /// Prints its flags
     #[derive(Strand)]
     struct FlagStrand {
+        /// Any number
         num: usize,
         #[strand(flag = "f1")]
         f1: Option<Trigger>,
+        /// Any string
         #[strand(flag = "f2")]
         f2: Option<String>,
     }
@@ -128,12 +184,21 @@ mod tests {
     impl Command for FlagStrand {
         type State = EmptyState;
 
-        type Err = String;
+        type Output = ();
 
-        fn action(self, _state: &mut Self::State) -> Result<(), Self::Err> {
-            println!("num: {}, f1: {:?}, f2: {:?}", self.num, self.f1, self.f2);
+        type Err = String;
 
-            Ok(())
+        fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            writeln!(
+                ctx,
+                "num: {}, f1: {:?}, f2: {:?}",
+                self.num, self.f1, self.f2
+            )
+            .map_err(|err| err.to_string())
         }
     }
 
@@ -149,10 +214,35 @@ mod tests {
     impl Command for DefaultStrand {
         type State = EmptyState;
 
+        type Output = ();
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            writeln!(ctx, "{}, {}, {}", self.num, self.d1, self.d2).map_err(|err| err.to_string())
+        }
+    }
+
+    #[derive(Strand)]
+    struct QuitStrand {}
+
+    impl Command for QuitStrand {
+        type State = EmptyState;
+
+        type Output = ();
+
         type Err = String;
 
-        fn action(self, _state: &mut Self::State) -> Result<(), Self::Err> {
-            println!("{}, {}, {}", self.num, self.d1, self.d2);
+        fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            ctx.exit();
 
             Ok(())
         }
@@ -163,22 +253,1716 @@ mod tests {
     enum ScopeStrand {
         #[strand(prefix = "$")]
         A(DefaultStrand),
+        /// Runs with flags
         #[strand(name = "flag")]
         B(FlagStrand),
+        #[strand(name = "quit")]
+        D(QuitStrand),
         #[strand(other)]
         C(TrailStrand),
     }
 
     #[test]
     fn strand_instance() {
-        run_console::<ScopeStrand>(
+        let console = Console::<ScopeStrand>::new()
+            .prompt("")
+            .counter_suffix(". ");
+
+        let (output, _) = run(
+            &console,
+            "$1\n$1 x 3\nflag 3 --f2 y --f1\n5 a b c\n5 -- a\nflag x\n",
+        );
+
+        assert_eq!(
+            output,
+            "1, abc, 2\n\
+             1, x, 3\n\
+             num: 3, f1: Some(Trigger), f2: Some(\"y\")\n\
+             number: 5 + trail args: a, b, c\n\
+             number: 5 + trail args: --, a\n\
+             !Unable to cast argument \"x\" (2)\n"
+        );
+    }
+
+    #[test]
+    fn quoted_arguments() {
+        let console = Console::<ScopeStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "$1 \"a b\" 3\n$1 'a;b'\n$1 a\\ b\nflag 2 --f2 \"say \\\"hi\\\"\"\n$1 \"a b\n",
+        );
+
+        assert_eq!(
+            output,
+            "1, a b, 3\n\
+             1, a;b, 2\n\
+             1, a b, 2\n\
+             num: 2, f1: None, f2: Some(\"say \\\"hi\\\"\")\n\
+             !Unterminated quote in \"\"a b\" (2)\n"
+        );
+
+        let matcher = Matcher::Single(MatcherSingle::Item(' '));
+        let mut input = Input::from_line(r#" plain "" 'C:\dir' a"b c"d "#, &matcher);
+
+        assert!(matches!(input.next_arg(), Ok(Some(Cow::Borrowed("plain")))));
+        assert_eq!(input.next_arg().unwrap().unwrap(), "");
+        assert_eq!(input.next_arg().unwrap().unwrap(), r"C:\dir");
+        assert_eq!(input.next_arg().unwrap().unwrap(), "ab cd");
+        assert!(input.next_arg().unwrap().is_none());
+    }
+
+    #[test]
+    fn help() {
+        let console = Console::<ScopeStrand>::new().prompt("");
+
+        let (output, _) = run(&console, "help\nhelp flag\nhelp $\nhelp nothing\n");
+
+        assert_eq!(
+            output,
+            "commands:\n\
+             \x20 $<num> [d1 = \"abc\".into()] [d2 = 2]\n\
+             \x20 flag <num> [--f1] [--f2 <f2>]        Runs with flags\n\
+             \x20 quit\n\
+             \x20 <num> [trail...]\n\
+             usage: flag <num> [--f1] [--f2 <f2>]\n\
+             Prints its flags\n\
+             \x20 <num>      Any number\n\
+             \x20 --f2 <f2>  Any string\n\
+             usage: $ <num> [d1 = \"abc\".into()] [d2 = 2]\n\
+             !No help found for \"nothing\"\n"
+        );
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    enum ManualScopeStrand {
+        #[strand(name = "manual")]
+        Manual(ManualImplStrand),
+    }
+
+    #[test]
+    fn completion() {
+        let console = Console::<ScopeStrand>::new();
+
+        let complete = |line: &str| {
+            let completion = console.complete(line, line.len());
+            let candidates: Vec<&str> = completion.candidates.iter().map(String::as_str).collect();
+            (completion.start, candidates.join(" "))
+        };
+
+        assert_eq!(complete(""), (0, "help $ flag quit".into()));
+        assert_eq!(complete("fl"), (0, "flag".into()));
+        assert_eq!(complete("flag 3 --"), (7, "--f1 --f2".into()));
+        assert_eq!(complete("flag 3 --f2 "), (12, "".into()));
+        assert_eq!(complete("flag 3 --f2 x --f"), (14, "--f1 --f2".into()));
+        assert_eq!(complete("$1; q"), (4, "quit".into()));
+        assert_eq!(complete("help fl"), (5, "flag".into()));
+
+        // The cursor is kept within the line and on the boundary of a character
+        assert_eq!(console.complete("fl", 10).candidates, vec!["flag"]);
+        assert_eq!(console.complete("é", 1).start, 0);
+        assert_eq!(console.complete("qé", 2).candidates, vec!["quit"]);
+
+        let completion = complete_line::<ScopeStrand>(
+            "flag 1 -",
+            8,
+            &Matcher::Single(MatcherSingle::Item(' ')),
+            &Matcher::Single(MatcherSingle::Item(';')),
+        );
+        assert_eq!(completion.candidates, vec!["--f1", "--f2"]);
+
+        // A Strand that doesn't implement Complete is completed from its help
+        let console = Console::<ManualScopeStrand>::new();
+        assert_eq!(console.complete("ma", 2).candidates, vec!["manual"]);
+        assert!(console.complete("manual -", 8).candidates.is_empty());
+    }
+
+    #[test]
+    fn exit_reasons() {
+        let console = Console::<ScopeStrand>::new()
+            .prompt("")
+            .exit_command("exit");
+
+        let (output, reason) = run(&console, "$1\nexit\n$2\n");
+        assert_eq!(output, "1, abc, 2\n");
+        assert_eq!(reason, ExitReason::ExitCommand);
+
+        let (output, reason) = run(&console, "$1; quit; $2\n$3\n");
+        assert_eq!(output, "1 1, abc, 2\n");
+        assert_eq!(reason, ExitReason::Requested);
+
+        let (output, reason) = run(&console, "$1\n");
+        assert_eq!(output, "1, abc, 2\n");
+        assert_eq!(reason, ExitReason::Eof);
+    }
+
+    #[derive(Strand)]
+    struct ShortFlagStrand {
+        #[strand(flag = "verbose", short = 'v')]
+        verbose: Option<Trigger>,
+        #[strand(flag = "quiet", short = 'q')]
+        quiet: Option<Trigger>,
+        #[strand(flag = "name", short = 'n')]
+        name: Option<String>,
+        #[strand(flag = "count")]
+        count: Option<usize>,
+    }
+
+    impl Command for ShortFlagStrand {
+        type State = EmptyState;
+
+        type Output = ();
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            writeln!(
+                ctx,
+                "{} {} {:?} {:?}",
+                self.verbose.is_some(),
+                self.quiet.is_some(),
+                self.name,
+                self.count
+            )
+            .map_err(|err| err.to_string())
+        }
+    }
+
+    #[test]
+    fn flag_forms() {
+        let console = Console::<ShortFlagStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "-v\n\
+             -vq\n\
+             --name=a --count=3\n\
+             -n b\n\
+             -qn=c\n\
+             -vn d\n\
+             --quiet --name e\n",
+        );
+
+        assert_eq!(
+            output,
+            "true false None None\n\
+             true true None None\n\
+             false false Some(\"a\") Some(3)\n\
+             false false Some(\"b\") None\n\
+             false true Some(\"c\") None\n\
+             true false Some(\"d\") None\n\
+             false true Some(\"e\") None\n"
+        );
+
+        let (output, _) = run(
+            &console,
+            "-x\n\
+             --xyz\n\
+             -nv a\n\
+             -v=a\n\
+             --quiet=a\n\
+             --count\n\
+             --count=x\n\
+             -n\n",
+        );
+
+        assert_eq!(
+            output,
+            "!Flag \"-x\" does not exist (1)\n\
+             !Flag \"--xyz\" does not exist (1)\n\
+             !Flag \"-n\" takes a value so must be last in \"-nv\" (1)\n\
+             !Flag \"-v\" does not take a value, given in \"-v=a\" (1)\n\
+             !Flag \"--quiet\" does not take a value, given in \"--quiet=a\" (1)\n\
+             !Expected a value for flag \"--count\" (1)\n\
+             !Unable to cast \"x\" for flag \"--count\" (1)\n\
+             !Expected a value for flag \"-n\" (1)\n"
+        );
+    }
+
+    #[derive(Strand)]
+    struct MixedStrand {
+        src: String,
+        #[strand(default = ".".into())]
+        dst: String,
+        #[strand(flag = "force", short = 'f')]
+        force: Option<Trigger>,
+        #[strand(trail)]
+        rest: String,
+    }
+
+    impl Command for MixedStrand {
+        type State = EmptyState;
+
+        type Output = ();
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            writeln!(
+                ctx,
+                "{} {} {} [{}]",
+                self.src,
+                self.dst,
+                self.force.is_some(),
+                self.rest
+            )
+            .map_err(|err| err.to_string())
+        }
+    }
+
+    /// A positional field declared after one with a default is still filled first
+    #[derive(Strand)]
+    struct DefaultFirstStrand {
+        #[strand(default = 1)]
+        a: u32,
+        b: u32,
+    }
+
+    impl Command for DefaultFirstStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            Ok(format!("{} {}", self.a, self.b))
+        }
+    }
+
+    #[test]
+    fn mixed_fields() {
+        let console = Console::<MixedStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "a\n\
+             -f a b\n\
+             a --force b c d\n\
+             a b -f\n\
+             a -- -f x\n\
+             a b -- -f\n\
+             \n\
+             -f\n\
+             a b --xyz\n",
+        );
+
+        assert_eq!(
+            output,
+            "a . false []\n\
+             a b true []\n\
+             a b true [c d]\n\
+             a b true []\n\
+             a -f false [x]\n\
+             a b false [-f]\n\
+             !Expected an argument (2)\n\
+             !Flag \"--xyz\" does not exist (3)\n"
+        );
+
+        let (output, _) = run(
+            &Console::<DefaultFirstStrand>::new().prompt(""),
+            "2\n\
+             2 3\n",
+        );
+        assert_eq!(output, "1 2\n3 2\n");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let console = Console::<ScopeStrand>::new().prompt("").diagnostics(true);
+
+        let (output, _) = run(&console, "flag x\nflag\n$1; flag 3 --f2\n");
+
+        assert_eq!(
+            output,
+            "!Unable to cast argument \"x\" (2)\n\
+             flag x\n\
+             \x20    ^ expected usize: invalid digit found in string\n\
+             !Expected an argument (2)\n\
+             flag\n\
+             \x20   ^ expected usize for \"num\"\n\
+             1 1, abc, 2\n\
+             2 !Expected a value for flag \"--f2\" (3)\n\
+             $1; flag 3 --f2\n\
+             \x20          ^^^^ expected a value\n"
+        );
+
+        let err = internal_err::<ScopeStrand>(&mut EmptyState, "flag 3 --f2 \"a b\" x");
+
+        assert_eq!(err.span, Some(18..19));
+        assert_eq!(err.field, None);
+        assert!(matches!(err.variant, error::ErrorType::Unexpected(ref arg) if arg == "x"));
+
+        let err = internal_err::<ScopeStrand>(&mut EmptyState, "flag -1");
+
+        assert_eq!(err.span, Some(5..7));
+        assert_eq!(
+            err.field,
+            Some(error::FieldInfo {
+                name: "num",
+                ty: "usize"
+            })
+        );
+        assert!(matches!(
+            err.variant,
+            error::ErrorType::Parse(error::ParseErr {
+                reason: Some(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn history() {
+        let console = Console::<ScopeStrand>::new()
+            .prompt("")
+            .history(History::new(3));
+
+        let (output, _) = run(&console, "$1\n!!\n$2\nhistory\n!2\n!1\n");
+
+        assert_eq!(
+            output,
+            "1, abc, 2\n\
+             $1\n\
+             1, abc, 2\n\
+             2, abc, 2\n\
+             2  $1\n\
+             3  $2\n\
+             4  history\n\
+             $1\n\
+             1, abc, 2\n\
+             !No history entry for \"!1\"\n"
+        );
+
+        let lines: Vec<(usize, String)> = console
+            .into_history()
+            .unwrap()
+            .iter()
+            .map(|(n, line)| (n, line.to_string()))
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![(3, "$2".into()), (4, "history".into()), (5, "$1".into())]
+        );
+
+        let path = std::env::temp_dir().join(format!("roped_history_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let console = Console::<ScopeStrand>::new()
+            .prompt("")
+            .history(History::load(&path, 10).unwrap());
+
+        run(&console, "$1\n$2\n");
+
+        let history = History::load(&path, 10).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.get(1), Some("$1"));
+        assert_eq!(history.last(), Some("$2"));
+    }
+
+    #[derive(Strand)]
+    struct SumStrand {
+        a: i64,
+        b: i64,
+    }
+
+    impl Command for SumStrand {
+        type State = EmptyState;
+
+        type Output = i64;
+
+        type Err = String;
+
+        fn action(self, _state: &mut Self::State, _ctx: &mut Context<'_>) -> Result<i64, String> {
+            Ok(self.a + self.b)
+        }
+    }
+
+    #[derive(Strand)]
+    struct ListStrand {
+        #[strand(trail)]
+        items: String,
+    }
+
+    impl Command for ListStrand {
+        type State = EmptyState;
+
+        type Output = Vec<String>;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<Vec<String>, String> {
+            Ok(self.items.split(' ').map(str::to_string).collect())
+        }
+    }
+
+    type MaybeSum = Option<i64>;
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(output = MaybeSum)]
+    enum OutputStrand {
+        #[strand(name = "sum")]
+        Sum(SumOutputStrand),
+        #[strand(name = "none")]
+        Nothing(NoneStrand),
+    }
+
+    #[derive(Strand)]
+    struct SumOutputStrand {
+        a: i64,
+        b: i64,
+    }
+
+    impl Command for SumOutputStrand {
+        type State = EmptyState;
+
+        type Output = Option<i64>;
+
+        type Err = String;
+
+        fn action(
+            self,
+            state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, String> {
+            SumStrand {
+                a: self.a,
+                b: self.b,
+            }
+            .action(state, ctx)
+            .map(Some)
+        }
+    }
+
+    #[derive(Strand)]
+    struct NoneStrand {}
+
+    impl Command for NoneStrand {
+        type State = EmptyState;
+
+        type Output = Option<i64>;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, String> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn outputs() {
+        let ws_chars = Matcher::Single(MatcherSingle::Item(' '));
+        let mut sink = Vec::new();
+        let mut ctx = Context::new(&mut sink);
+
+        let sum = SumStrand::run(
+            &mut EmptyState,
+            &mut ctx,
+            Input::from_line("2 -5", &ws_chars),
+            1,
+        );
+        assert_eq!(sum.ok(), Some(-3));
+
+        let sum = OutputStrand::run(
             &mut EmptyState,
-            "> ".into(),
-            ". ".into(),
-            "!".into(),
-            Matcher::Single(MatcherSingle::Item(' ')),
-            Matcher::List(&[MatcherSingle::Item('\n'), MatcherSingle::Item(';')]),
-        )
-        .unwrap();
+            &mut ctx,
+            Input::from_line("sum 1 2", &ws_chars),
+            1,
+        );
+        assert_eq!(sum.ok(), Some(Some(3)));
+
+        let none = OutputStrand::run(
+            &mut EmptyState,
+            &mut ctx,
+            Input::from_line("none", &ws_chars),
+            1,
+        );
+        assert_eq!(none.ok(), Some(None));
+
+        assert!(sink.is_empty());
+
+        let (output, _) = run(
+            &Console::<OutputStrand>::new().prompt(""),
+            "sum 4 5; none\n",
+        );
+        assert_eq!(output, "1 9\n");
+
+        let (output, _) = run(&Console::<ListStrand>::new().prompt(""), "a b c\n");
+        assert_eq!(output, "a\nb\nc\n");
+    }
+
+    #[test]
+    fn arguments() {
+        let args = ["a b", "c", "", "say \"hi\"", "C:\\dir", "it's"];
+
+        let list = input::ArgList::new(args);
+        assert_eq!(list.line(), r#"a\ b c "" say\ \"hi\" C:\\dir it\'s"#);
+
+        let mut input = Input::from_args(&list);
+
+        let mut split = Vec::new();
+        while let Some(arg) = input.next_arg().unwrap() {
+            split.push(arg.into_owned());
+        }
+        assert_eq!(split, args);
+
+        // The rest of the input keeps the arguments apart for a trail
+        let list = input::ArgList::new(["5", "a b", "\"c\""]);
+        let mut input = Input::from_args(&list);
+        assert_eq!(input.next_arg().unwrap().unwrap(), "5");
+
+        let ws_chars = Matcher::Single(MatcherSingle::Item(' '));
+        let mut trail = Input::from_line(input.take_rest(), &ws_chars);
+
+        let mut split = Vec::new();
+        while let Some(arg) = trail.next_arg().unwrap() {
+            split.push(arg.into_owned());
+        }
+        assert_eq!(split, ["a b", "\"c\""]);
+
+        let mut sink = Vec::new();
+
+        let sum = args::run_args_with::<SumStrand>(&mut EmptyState, ["prog", "2", "3"], &mut sink);
+        assert_eq!(sum.ok(), Some(5));
+
+        let err = args::run_args_with::<SumStrand>(&mut EmptyState, ["prog", "2"], &mut sink);
+        assert_eq!(err.unwrap_err().exit_code(), 2);
+
+        let err = args::run_args_with::<ManualImplStrand>(&mut EmptyState, ["prog"], &mut sink);
+        assert_eq!(err.unwrap_err().exit_code(), 1);
+
+        args::run_args_with::<ManualImplStrand>(&mut EmptyState, ["prog", "a b", "c"], &mut sink)
+            .unwrap();
+        args::run_args_with::<ScopeStrand>(&mut EmptyState, ["prog", "$1", "x", "3"], &mut sink)
+            .unwrap();
+        args::run_args_with::<ScopeStrand>(&mut EmptyState, ["prog", "$", "2"], &mut sink).unwrap();
+        assert_eq!(
+            String::from_utf8(sink).unwrap(),
+            "a b + c\n\
+             1, x, 3\n\
+             2, abc, 2\n"
+        );
+    }
+
+    #[test]
+    fn script() {
+        let script = "# Adds up the numbers\n\
+                      sum 1 2\n\
+                      \n\
+                      \x20 # Indented comments are skipped too\n\
+                      sum 3 4; sum x 1; sum 5 6\n\
+                      sum 7\n\
+                      none\n";
+
+        let mut output = Vec::new();
+
+        let report = script::Script::<OutputStrand>::new()
+            .run_str(&mut EmptyState, script, &mut output)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "3\n7\n!Line 5, command 2: Unable to cast argument \"x\" (2)\n"
+        );
+        assert!(report.stopped);
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|err| (err.line, err.command))
+                .collect::<Vec<_>>(),
+            vec![(5, 2)]
+        );
+
+        let mut output = Vec::new();
+
+        let report = script::Script::<OutputStrand>::new()
+            .on_error(script::OnError::Continue)
+            .run_str(&mut EmptyState, script, &mut output)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "3\n7\n!Line 5, command 2: Unable to cast argument \"x\" (2)\n11\n\
+             !Line 6, command 1: Expected an argument (3)\n"
+        );
+        assert!(!report.stopped);
+        assert!(!report.is_ok());
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|err| (err.line, err.command))
+                .collect::<Vec<_>>(),
+            vec![(5, 2), (6, 1)]
+        );
+    }
+
+    fn even(value: &usize) -> Result<(), String> {
+        match value % 2 {
+            0 => Ok(()),
+            _ => Err("must be even".to_string()),
+        }
+    }
+
+    #[derive(Strand)]
+    struct ValidatedStrand {
+        #[strand(range = 1..=64)]
+        size: usize,
+        #[strand(choices = ["fast", "slow"])]
+        speed: String,
+        #[strand(flag = "name", short = 'n', min_len = 3)]
+        name: Option<String>,
+        #[strand(default = 2, validate = even)]
+        step: usize,
+    }
+
+    impl Command for ValidatedStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            Ok(format!(
+                "{} {} {:?} {}",
+                self.size, self.speed, self.name, self.step
+            ))
+        }
+    }
+
+    #[test]
+    fn validation() {
+        let console = Console::<ValidatedStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "8 fast\n\
+             64 slow --name abc 4\n\
+             65 fast\n\
+             8 medium\n\
+             8 slow -n ab\n\
+             8 slow 3\n",
+        );
+
+        assert_eq!(
+            output,
+            "8 fast None 2\n\
+             64 slow Some(\"abc\") 4\n\
+             !Invalid value \"65\" for \"size\", must be in 1..=64 (1)\n\
+             !Invalid value \"medium\" for \"speed\", must be one of \"fast\", \"slow\" (2)\n\
+             !Invalid value \"ab\" for \"name\", must be at least 3 characters long (4)\n\
+             !Invalid value \"3\" for \"step\", must be even (3)\n"
+        );
+
+        let err = internal_err::<ValidatedStrand>(&mut EmptyState, "0 fast");
+
+        assert_eq!(err.span, Some(0..1));
+        assert!(matches!(
+            err.variant,
+            error::ErrorType::Validation(error::ValidationErr {
+                field: "size",
+                rule: error::Rule::Range("1..=64"),
+                ..
+            })
+        ));
+        assert_eq!(
+            err.diagnostic("0 fast").unwrap(),
+            "0 fast\n^ must be in 1..=64"
+        );
+    }
+
+    struct Users {
+        names: Vec<&'static str>,
+    }
+
+    #[derive(Debug)]
+    struct UserId(usize);
+
+    impl Arg<Users> for UserId {
+        type Err = String;
+
+        fn parse_arg(arg: &str, state: &Users) -> Result<Self, Self::Err> {
+            match state.names.iter().position(|name| *name == arg) {
+                Some(id) => Ok(UserId(id)),
+                None => Err(format!("no user named {}", arg)),
+            }
+        }
+    }
+
+    fn parse_secs(arg: &str) -> Result<std::time::Duration, std::num::ParseIntError> {
+        arg.parse().map(std::time::Duration::from_secs)
+    }
+
+    #[derive(Strand)]
+    struct WhoisStrand {
+        #[strand(arg)]
+        user: UserId,
+        #[strand(flag = "timeout", short = 't', parse_with = parse_secs)]
+        timeout: Option<std::time::Duration>,
+    }
+
+    impl Command for WhoisStrand {
+        type State = Users;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(self, state: &mut Self::State, _ctx: &mut Context<'_>) -> Result<String, String> {
+            Ok(format!(
+                "{:?} {} {:?}",
+                self.user, state.names[self.user.0], self.timeout
+            ))
+        }
+    }
+
+    #[test]
+    fn custom_parsers() {
+        let console = Console::<WhoisStrand>::new().prompt("");
+
+        let mut users = Users {
+            names: vec!["root", "admin"],
+        };
+        let mut output = Vec::new();
+
+        console
+            .run_with(
+                &mut users,
+                &mut "admin -t 5\nroot\nguest\nroot --timeout=soon\n".as_bytes(),
+                &mut output,
+            )
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "UserId(1) admin Some(5s)\n\
+             UserId(0) root None\n\
+             !Unable to cast argument \"guest\" (1)\n\
+             !Unable to cast \"soon\" for flag \"--timeout\" (2)\n"
+        );
+
+        let err = internal_err::<WhoisStrand>(&mut users, "guest");
+
+        assert_eq!(err.label(), "expected UserId: no user named guest");
+    }
+
+    #[derive(Strand)]
+    struct ManyStrand {
+        op: String,
+        #[strand(flag = "sep", short = 's')]
+        sep: Option<String>,
+        /// The numbers to use
+        #[strand(many, min = 1, max = 3)]
+        nums: Vec<i64>,
+    }
+
+    impl Command for ManyStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            let nums: Vec<String> = self.nums.iter().map(i64::to_string).collect();
+            let sep = self.sep.unwrap_or(" ".to_string());
+
+            Ok(format!("{}: {}", self.op, nums.join(&sep)))
+        }
+    }
+
+    #[test]
+    fn many() {
+        let console = Console::<ManyStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "add 1 2 3\n\
+             add 1 -s , 2\n\
+             add -- -1 -2\n\
+             add\n\
+             add 1 x 3\n\
+             add 1 2 3 4\n\
+             help\n",
+        );
+
+        assert_eq!(
+            output,
+            "add: 1 2 3\n\
+             add: 1,2\n\
+             add: -1 -2\n\
+             !Expected an argument (2)\n\
+             !Unable to cast argument \"x\" (3)\n\
+             !Did not expect an argument \"4\" (5)\n\
+             usage: <op> [-s, --sep <sep>] <nums>...\n\
+             \x20 <nums>...  The numbers to use\n"
+        );
+
+        let err = internal_err::<ManyStrand>(&mut EmptyState, "add 1 x");
+
+        assert_eq!(err.index, 3);
+        assert_eq!(err.span, Some(6..7));
+        assert_eq!(err.field.unwrap().ty, "i64");
+    }
+
+    #[derive(Strand)]
+    struct RepeatedFlagStrand {
+        #[strand(flag = "tag", short = 't')]
+        tags: Vec<String>,
+        #[strand(flag = "verbose", short = 'v', count)]
+        verbose: u8,
+        #[strand(flag = "out", short = 'o', once)]
+        out: Option<String>,
+    }
+
+    impl Command for RepeatedFlagStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            Ok(format!("{:?} {} {:?}", self.tags, self.verbose, self.out))
+        }
+    }
+
+    #[test]
+    fn repeated_flags() {
+        let console = Console::<RepeatedFlagStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "--tag a --tag b\n\
+             -t a -vt=b\n\
+             -vvv --verbose -o x\n\
+             -o x --out y\n\
+             -vv=a\n\
+             help\n",
+        );
+
+        assert_eq!(
+            output,
+            "[\"a\", \"b\"] 0 None\n\
+             [\"a\", \"b\"] 1 None\n\
+             [] 4 Some(\"x\")\n\
+             !Flag \"--out\" was given more than once (3)\n\
+             !Flag \"-v\" does not take a value, given in \"-vv=a\" (1)\n\
+             usage: [-t, --tag <tags>] [-v, --verbose] [-o, --out <out>]\n"
+        );
+    }
+
+    #[derive(Strand)]
+    struct RequiredFlagStrand {
+        /// How to copy
+        #[strand(flag = "mode", short = 'm', required)]
+        mode: String,
+        #[strand(flag = "retries", default = 3)]
+        retries: u8,
+    }
+
+    impl Command for RequiredFlagStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            Ok(format!("{} {}", self.mode, self.retries))
+        }
+    }
+
+    #[test]
+    fn required_flags() {
+        let console = Console::<RequiredFlagStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "--mode fast\n\
+             -m slow --retries 5\n\
+             --retries 5\n\
+             --retries x -m fast\n\
+             help\n",
+        );
+
+        assert_eq!(
+            output,
+            "fast 3\n\
+             slow 5\n\
+             !Expected flag \"--mode\" (3)\n\
+             !Unable to cast \"x\" for flag \"--retries\" (2)\n\
+             usage: -m, --mode <mode> [--retries <retries> = 3]\n\
+             \x20 -m, --mode <mode>  How to copy\n"
+        );
+
+        let err = internal_err::<RequiredFlagStrand>(&mut EmptyState, "--retries 1");
+
+        assert!(matches!(err.variant, error::ErrorType::MissingFlag(ref flag) if flag == "--mode"));
+        assert_eq!(err.label(), "expected flag --mode");
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(output = String)]
+    enum ShapeStrand {
+        #[strand(name = "ping")]
+        Ping(PingStrand),
+        #[strand(name = "move")]
+        Move(MoveStrand),
+    }
+
+    /// Replies with pong
+    #[derive(Strand)]
+    struct PingStrand;
+
+    impl Command for PingStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            Ok("pong".to_string())
+        }
+    }
+
+    #[derive(Strand)]
+    struct MoveStrand(i32, i32, #[strand(flag = "speed")] Option<u8>);
+
+    impl Command for MoveStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            Ok(format!("{} {} {:?}", self.0, self.1, self.2))
+        }
+    }
+
+    #[test]
+    fn struct_shapes() {
+        let console = Console::<ShapeStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "ping\n\
+             ping 1\n\
+             move 1 -2\n\
+             move --speed 3 4 5\n\
+             move 1\n\
+             help\n",
+        );
+
+        assert_eq!(
+            output,
+            "pong\n\
+             !Did not expect an argument \"1\" (2)\n\
+             1 -2 None\n\
+             4 5 Some(3)\n\
+             !Expected an argument (3)\n\
+             commands:\n\
+             \x20 ping\n\
+             \x20 move <arg0> <arg1> [--speed <arg2>]\n"
+        );
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(case_insensitive)]
+    enum AliasStrand {
+        /// Runs with flags
+        #[strand(name = "flag", alias = "f", alias = "fl")]
+        Flag(FlagStrand),
+        #[strand(name = "Quit", alias = "q")]
+        Quit(QuitStrand),
+    }
+
+    #[test]
+    fn aliases() {
+        let console = Console::<AliasStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "FLAG 1\n\
+             f 2 --f1\n\
+             Fl 3 --f2 x\n\
+             flg 4\n\
+             help\n\
+             help f\n\
+             help quit\n\
+             help FL\n\
+             q\n\
+             flag 5\n",
+        );
+
+        assert_eq!(
+            output,
+            "num: 1, f1: None, f2: None\n\
+             num: 2, f1: Some(Trigger), f2: None\n\
+             num: 3, f1: None, f2: Some(\"x\")\n\
+             !Scope \"flg\" does not exist, did you mean \"flag\" or \"fl\"? (1)\n\
+             commands:\n\
+             \x20 flag <num> [--f1] [--f2 <f2>]  Runs with flags\n\
+             \x20 Quit\n\
+             usage: f <num> [--f1] [--f2 <f2>]\n\
+             Prints its flags\n\
+             \x20 <num>      Any number\n\
+             \x20 --f2 <f2>  Any string\n\
+             usage: quit\n\
+             usage: FL <num> [--f1] [--f2 <f2>]\n\
+             Prints its flags\n\
+             \x20 <num>      Any number\n\
+             \x20 --f2 <f2>  Any string\n"
+        );
+
+        let complete = |line: &str| console.complete(line, line.len()).candidates.join(" ");
+
+        assert_eq!(complete(""), "help flag Quit");
+        assert_eq!(complete("f"), "flag f");
+        assert_eq!(complete("fl 1 --"), "--f1 --f2");
+        assert_eq!(complete("F"), "flag f");
+        assert_eq!(complete("qu"), "Quit");
+        assert_eq!(complete("FLAG 1 -"), "--f1 --f2");
+    }
+
+    #[test]
+    fn suggestions() {
+        let (output, _) = run(
+            &Console::<AliasStrand>::new().prompt(""),
+            "qiut\n\
+             FLGA 1\n\
+             xyz\n",
+        );
+
+        assert_eq!(
+            output,
+            "!Scope \"qiut\" does not exist, did you mean \"Quit\"? (1)\n\
+             !Scope \"FLGA\" does not exist, did you mean \"flag\"? (1)\n\
+             !Scope \"xyz\" does not exist (1)\n"
+        );
+
+        let (output, _) = run(
+            &Console::<RepeatedFlagStrand>::new().prompt(""),
+            "--tga a\n\
+             --verbsoe\n\
+             --x\n",
+        );
+
+        assert_eq!(
+            output,
+            "!Flag \"--tga\" does not exist, did you mean \"--tag\"? (1)\n\
+             !Flag \"--verbsoe\" does not exist, did you mean \"--verbose\"? (1)\n\
+             !Flag \"--x\" does not exist (1)\n"
+        );
+
+        assert_eq!(
+            error::suggestions("lst", &["list", "last", "log"]),
+            vec!["list", "last"]
+        );
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    enum InlineStrand {
+        /// Adds two numbers
+        #[strand(name = "add")]
+        Add {
+            a: i64,
+            b: i64,
+            /// Negates the sum
+            #[strand(flag = "neg", short = 'n')]
+            neg: Option<Trigger>,
+        },
+        #[strand(name = "move")]
+        Move(i32, i32),
+        /// Says hello
+        #[strand(name = "hello", alias = "hi", handler = Self::hello)]
+        Hello,
+        #[strand(name = "quit")]
+        Quit(QuitStrand),
+    }
+
+    impl InlineStrand {
+        fn hello(self, _state: &mut EmptyState, ctx: &mut Context<'_>) -> Result<(), String> {
+            writeln!(ctx, "hello").map_err(|err| err.to_string())
+        }
+    }
+
+    impl Command for InlineStrand {
+        type State = EmptyState;
+
+        type Output = ();
+
+        type Err = String;
+
+        fn action(
+            self,
+            state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            match self {
+                InlineStrand::Add { a, b, neg: None } => writeln!(ctx, "{}", a + b),
+                InlineStrand::Add { a, b, neg: Some(_) } => writeln!(ctx, "{}", -(a + b)),
+                InlineStrand::Move(x, y) => writeln!(ctx, "moved to {}, {}", x, y),
+                InlineStrand::Hello => return self.hello(state, ctx),
+                InlineStrand::Quit(quit) => return quit.action(state, ctx),
+            }
+            .map_err(|err| err.to_string())
+        }
+    }
+
+    #[test]
+    fn inline_variants() {
+        let console = Console::<InlineStrand>::new().prompt("");
+
+        let (output, reason) = run(
+            &console,
+            "add 1 2\n\
+             add -n 1 2\n\
+             move 3 -4\n\
+             hi\n\
+             hello there\n\
+             add 1\n\
+             help\n\
+             help add\n\
+             quit\n\
+             hello\n",
+        );
+
+        assert_eq!(
+            output,
+            "3\n\
+             -3\n\
+             moved to 3, -4\n\
+             hello\n\
+             !Did not expect an argument \"there\" (2)\n\
+             !Expected an argument (3)\n\
+             commands:\n\
+             \x20 add <a> <b> [-n, --neg]  Adds two numbers\n\
+             \x20 move <arg0> <arg1>\n\
+             \x20 hello                    Says hello\n\
+             \x20 quit\n\
+             usage: add <a> <b> [-n, --neg]\n\
+             Adds two numbers\n\
+             \x20 -n, --neg  Negates the sum\n"
+        );
+        assert_eq!(reason, ExitReason::Requested);
+
+        let complete = |line: &str| console.complete(line, line.len()).candidates.join(" ");
+
+        assert_eq!(complete("add 1 2 -"), "--neg");
+        assert_eq!(complete("h"), "help hello");
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TallyError {
+        Message(String),
+        DivideByZero,
+    }
+
+    impl From<String> for TallyError {
+        fn from(value: String) -> Self {
+            TallyError::Message(value)
+        }
+    }
+
+    impl std::fmt::Display for TallyError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                TallyError::Message(message) => write!(f, "{}", message),
+                TallyError::DivideByZero => write!(f, "cannot divide by zero"),
+            }
+        }
+    }
+
+    struct DivideByZero;
+
+    impl From<DivideByZero> for TallyError {
+        fn from(_: DivideByZero) -> Self {
+            TallyError::DivideByZero
+        }
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(state = i64, output = i64, error = TallyError)]
+    enum TallyStrand {
+        #[strand(name = "add")]
+        Add(TallyAddStrand),
+        #[strand(name = "div")]
+        Div(TallyDivStrand),
+        #[strand(name = "set")]
+        Set(TallySetStrand),
+    }
+
+    #[derive(Strand)]
+    #[strand(state = i64, output = i64, handler = tally_add)]
+    struct TallyAddStrand {
+        amount: i64,
+    }
+
+    fn tally_add(
+        this: TallyAddStrand,
+        state: &mut i64,
+        _: &mut Context<'_>,
+    ) -> Result<i64, String> {
+        *state = state
+            .checked_add(this.amount)
+            .ok_or_else(|| "overflow".to_string())?;
+        Ok(*state)
+    }
+
+    #[derive(Strand)]
+    #[strand(state = i64, output = i64, error = DivideByZero, handler = TallyDivStrand::run_div)]
+    struct TallyDivStrand {
+        by: i64,
+    }
+
+    impl TallyDivStrand {
+        fn run_div(self, state: &mut i64, _: &mut Context<'_>) -> Result<i64, DivideByZero> {
+            *state = state.checked_div(self.by).ok_or(DivideByZero)?;
+            Ok(*state)
+        }
+    }
+
+    /// Converts its error into that of the Strand with `From`
+    #[derive(Strand)]
+    #[strand(error = TallyError)]
+    struct TallySetStrand {
+        value: i64,
+    }
+
+    impl Command for TallySetStrand {
+        type State = i64;
+
+        type Output = i64;
+
+        type Err = String;
+
+        fn action(self, state: &mut i64, _: &mut Context<'_>) -> Result<i64, String> {
+            *state = self.value;
+            Ok(*state)
+        }
+    }
+
+    #[test]
+    fn handlers() {
+        let ws_chars = Matcher::Single(MatcherSingle::Item(' '));
+        let mut sink = Vec::new();
+        let mut ctx = Context::new(&mut sink);
+
+        let mut tally = |state: &mut i64, line: &str| {
+            TallyStrand::run(state, &mut ctx, Input::from_line(line, &ws_chars), 1).map_err(|err| {
+                match err {
+                    Error::Err(err) => err,
+                    Error::Internal(err) => TallyError::Message(err.to_string()),
+                }
+            })
+        };
+
+        let mut state = 0;
+
+        assert_eq!(tally(&mut state, "add 10"), Ok(10));
+        assert_eq!(tally(&mut state, "div 4"), Ok(2));
+        assert_eq!(tally(&mut state, "div 0"), Err(TallyError::DivideByZero));
+        assert_eq!(tally(&mut state, "set 9223372036854775807"), Ok(i64::MAX));
+        assert_eq!(
+            tally(&mut state, "add 1"),
+            Err(TallyError::Message("overflow".to_string()))
+        );
+        assert_eq!(
+            tally(&mut state, "div x"),
+            Err(TallyError::Message(
+                "Unable to cast argument \"x\" (2)".to_string()
+            ))
+        );
+        assert_eq!(state, i64::MAX);
+    }
+
+    trait Store {
+        fn set(&mut self, key: String, value: String);
+
+        fn get(&self, key: &str) -> Option<&str>;
+    }
+
+    impl Store for Vec<(String, String)> {
+        fn set(&mut self, key: String, value: String) {
+            self.push((key, value));
+        }
+
+        fn get(&self, key: &str) -> Option<&str> {
+            self.iter()
+                .rev()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    impl Store for std::collections::HashMap<String, String> {
+        fn set(&mut self, key: String, value: String) {
+            self.insert(key, value);
+        }
+
+        fn get(&self, key: &str) -> Option<&str> {
+            self.get(key).map(String::as_str)
+        }
+    }
+
+    /// A key that has been set in the store
+    struct Key<S> {
+        name: String,
+        store: std::marker::PhantomData<S>,
+    }
+
+    impl<S: Store> Arg<S> for Key<S> {
+        type Err = String;
+
+        fn parse_arg(arg: &str, state: &S) -> Result<Self, Self::Err> {
+            match state.get(arg) {
+                Some(_) => Ok(Key {
+                    name: arg.to_string(),
+                    store: std::marker::PhantomData,
+                }),
+                None => Err(format!("\"{}\" is not set", arg)),
+            }
+        }
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(state = S, output = String)]
+    enum AdminStrand<S: Store> {
+        #[strand(name = "set")]
+        Set(SetStrand<S>),
+        #[strand(name = "get")]
+        Get(GetStrand<S>),
+    }
+
+    #[derive(Strand)]
+    struct SetStrand<S: Store> {
+        key: String,
+        value: String,
+        /// Replaces a key that is already set
+        #[strand(flag = "replace", short = 'r', arg)]
+        replace: Option<Key<S>>,
+    }
+
+    impl<S: Store> Command for SetStrand<S> {
+        type State = S;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(self, state: &mut S, _ctx: &mut Context<'_>) -> Result<String, String> {
+            let key = match self.replace {
+                Some(key) => key.name,
+                None => self.key,
+            };
+
+            state.set(key, self.value);
+            Ok("ok".to_string())
+        }
+    }
+
+    #[derive(Strand)]
+    struct GetStrand<S>
+    where
+        S: Store,
+    {
+        #[strand(arg)]
+        key: Key<S>,
+    }
+
+    impl<S: Store> Command for GetStrand<S> {
+        type State = S;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(self, state: &mut S, _ctx: &mut Context<'_>) -> Result<String, String> {
+            Ok(state.get(&self.key.name).unwrap_or_default().to_string())
+        }
+    }
+
+    fn run_admin<S: Store>(state: &mut S, line: &str) -> Result<String, String> {
+        let ws_chars = Matcher::Single(MatcherSingle::Item(' '));
+        let mut sink = Vec::new();
+        let mut ctx = Context::new(&mut sink);
+
+        AdminStrand::<S>::run(state, &mut ctx, Input::from_line(line, &ws_chars), 1)
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn generics() {
+        let mut list: Vec<(String, String)> = Vec::new();
+        let mut map: std::collections::HashMap<String, String> = Default::default();
+
+        let ok = |v: &str| Ok(v.to_string());
+
+        assert_eq!(run_admin(&mut list, "set a 1"), ok("ok"));
+        assert_eq!(run_admin(&mut list, "get a"), ok("1"));
+        assert_eq!(run_admin(&mut map, "set b 2"), ok("ok"));
+        assert_eq!(run_admin(&mut map, "set c 3 -r b"), ok("ok"));
+        assert_eq!(run_admin(&mut map, "get b"), ok("3"));
+        assert_eq!(
+            run_admin(&mut map, "get a"),
+            Err("Unable to cast argument \"a\" (2)".to_string())
+        );
+
+        let mut sink = Vec::new();
+        AdminStrand::<Vec<(String, String)>>::help()
+            .write("", &mut sink)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(sink).unwrap(),
+            "commands:\n\
+             \x20 set <key> <value> [-r, --replace <replace>]\n\
+             \x20 get <key>\n"
+        );
+    }
+
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(output = String)]
+    enum ReservedStrand {
+        #[strand(name = "echo")]
+        Echo(EchoStrand),
+    }
+
+    #[derive(Strand)]
+    struct EchoStrand {
+        state: String,
+        ctx: String,
+        this: String,
+        arg: String,
+        position: String,
+        #[strand(flag = "index", short = 'i')]
+        index: Option<usize>,
+        #[strand(flag = "ended")]
+        flags_ended: Option<Trigger>,
+        #[strand(trail)]
+        trail_span: String,
+    }
+
+    impl Command for EchoStrand {
+        type State = EmptyState;
+
+        type Output = String;
+
+        type Err = String;
+
+        fn action(
+            self,
+            _state: &mut Self::State,
+            _ctx: &mut Context<'_>,
+        ) -> Result<String, String> {
+            Ok(format!(
+                "{} {} {} {} {} {:?} {} [{}]",
+                self.state,
+                self.ctx,
+                self.this,
+                self.arg,
+                self.position,
+                self.index,
+                self.flags_ended.is_some(),
+                self.trail_span
+            ))
+        }
+    }
+
+    #[test]
+    fn reserved_names() {
+        let console = Console::<ReservedStrand>::new().prompt("");
+
+        let (output, _) = run(
+            &console,
+            "echo a b c d e\n\
+             echo -i 1 a b c d --ended e f g\n",
+        );
+
+        assert_eq!(
+            output,
+            "a b c d e None false []\n\
+             a b c d e Some(1) true [f g]\n"
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[derive(Strand)]
+    #[strand(async)]
+    struct AsyncAddStrand {
+        amount: usize,
+    }
+
+    #[cfg(feature = "async")]
+    impl asynchronous::AsyncCommand for AsyncAddStrand {
+        type State = usize;
+
+        type Output = ();
+
+        type Err = String;
+
+        async fn action(
+            self,
+            state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            tokio::task::yield_now().await;
+
+            *state += self.amount;
+            writeln!(ctx, "{}", state).map_err(|err| err.to_string())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[derive(Strand)]
+    #[strand(async)]
+    struct AsyncQuitStrand {}
+
+    #[cfg(feature = "async")]
+    impl asynchronous::AsyncCommand for AsyncQuitStrand {
+        type State = usize;
+
+        type Output = ();
+
+        type Err = String;
+
+        async fn action(
+            self,
+            _state: &mut Self::State,
+            ctx: &mut Context<'_>,
+        ) -> Result<Self::Output, Self::Err> {
+            ctx.exit();
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[allow(unused)]
+    #[derive(Strand)]
+    #[strand(state = usize, error = String, async)]
+    enum AsyncScopeStrand {
+        /// Adds to the total
+        #[strand(name = "add")]
+        Add(AsyncAddStrand),
+        #[strand(name = "quit")]
+        Quit(AsyncQuitStrand),
+        #[strand(name = "reset", handler = reset)]
+        Reset,
+    }
+
+    #[cfg(feature = "async")]
+    async fn reset(
+        _this: AsyncScopeStrand,
+        state: &mut usize,
+        _ctx: &mut Context<'_>,
+    ) -> Result<(), String> {
+        *state = 0;
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_console() {
+        let console = Console::<AsyncScopeStrand>::new()
+            .prompt("")
+            .exit_command("exit");
+
+        let mut state = 0;
+        let mut output = Vec::new();
+
+        let reason = console
+            .run_async(
+                &mut state,
+                &mut &b"add 2\nadd 3; add x\nhelp add\nadd 1; quit; add 4\nadd 5\n"[..],
+                &mut output,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2\n\
+             1 5\n\
+             2 !Unable to cast argument \"x\" (2)\n\
+             usage: add <amount>\n\
+             1 6\n"
+        );
+        assert_eq!(reason, ExitReason::Requested);
+        assert_eq!(state, 6);
+
+        let mut output = Vec::new();
+
+        let reason = console
+            .run_async(
+                &mut state,
+                &mut &b"add 1\nreset\nadd 2\nexit\nadd 1\n"[..],
+                &mut output,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "7\n2\n");
+        assert_eq!(reason, ExitReason::ExitCommand);
+        assert_eq!(state, 2);
     }
 }
@@ -0,0 +1,77 @@
+use std::io::{self, Write};
+
+/// How the console prints the value returned by a Strand
+///
+/// Render is like [`Display`](std::fmt::Display), but a value can choose to print nothing at
+/// all, as `()` and `None` do, and is responsible for its own trailing newline
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use roped::Render;
+///
+/// struct Total(usize);
+///
+/// impl Render for Total {
+///     fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+///         writeln!(out, "total: {}", self.0)
+///     }
+/// }
+/// ```
+pub trait Render {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Nothing is printed for a Strand without an output
+impl Render for () {
+    fn render(&self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: Render + ?Sized> Render for &T {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        (**self).render(out)
+    }
+}
+
+impl<T: Render + ?Sized> Render for Box<T> {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        (**self).render(out)
+    }
+}
+
+/// Nothing is printed for `None`
+impl<T: Render> Render for Option<T> {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Some(v) => v.render(out),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Each value is printed in turn
+impl<T: Render> Render for Vec<T> {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        self.iter().try_for_each(|v| v.render(out))
+    }
+}
+
+macro_rules! render_display {
+    ($($t:ty),*) => {
+        $(
+            /// Printed using its [`Display`](std::fmt::Display) on a line of its own
+            impl Render for $t {
+                fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+                    writeln!(out, "{}", self)
+                }
+            }
+        )*
+    };
+}
+
+render_display!(
+    str, String, bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32,
+    f64
+);
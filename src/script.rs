@@ -0,0 +1,255 @@
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+use parsr::parser_matcher::{Matcher, MatcherSingle};
+
+use crate::{
+    console::split_commands, context::Context, error::Error, input::Input, render::Render,
+    strand::Strand,
+};
+
+/// What a script does when one of its commands fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OnError {
+    /// Stops running the script at the first error
+    #[default]
+    Stop,
+    /// Runs the rest of the script, collecting every error
+    Continue,
+}
+
+/// An error from a command in a script, along with where the command is in the script
+#[derive(Debug)]
+pub struct ScriptError<Err> {
+    /// The line of the command, counting from 1
+    pub line: usize,
+    /// The index of the command on its line, counting from 1
+    pub command: usize,
+    pub error: Error<Err>,
+}
+impl<Err> Display for ScriptError<Err>
+where
+    Err: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Line {}, command {}: {}",
+            self.line, self.command, self.error
+        )
+    }
+}
+
+/// What happened when a script was run
+#[derive(Debug)]
+pub struct Report<Err> {
+    /// The errors of the commands that failed, in the order they were run
+    pub errors: Vec<ScriptError<Err>>,
+    /// Whether the script stopped before its end, because of an error or a command asking to
+    /// exit through its [`Context`]
+    pub stopped: bool,
+}
+impl<Err> Report<Err> {
+    /// Whether every command that was run succeeded
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Runs a script of console commands using the provided Strand, for procedures that are kept in
+/// text files rather than typed in
+///
+/// Every line of the script is run in turn, separating its commands as a [`Console`] would, while
+/// blank lines and lines beginning with the comment prefix are skipped
+///
+/// | option           | default           |
+/// |------------------|-------------------|
+/// | `comment_prefix` | `"#"`             |
+/// | `on_error`       | [`OnError::Stop`] |
+/// | `err_prefix`     | `"!"`             |
+/// | `ws_chars`       | `' '`             |
+/// | `nl_chars`       | `';'`             |
+///
+/// # Examples
+///
+/// ```no_run
+/// use roped::{script::{OnError, Script}, Context, EmptyState, Error, Input, Strand};
+/// # struct Commands;
+/// # impl Strand for Commands {
+/// #     type State = EmptyState;
+/// #     type Output = ();
+/// #     type Err = String;
+/// #     fn run(
+/// #         _: &mut EmptyState,
+/// #         _: &mut Context<'_>,
+/// #         _: Input<'_>,
+/// #         _: usize,
+/// #     ) -> Result<(), Error<String>> {
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// let report = Script::<Commands>::new()
+///     .on_error(OnError::Continue)
+///     .run_file(&mut EmptyState, "maintenance.txt", &mut std::io::stdout())
+///     .unwrap();
+///
+/// for err in &report.errors {
+///     eprintln!("{}", err);
+/// }
+/// ```
+///
+/// [`Console`]: crate::console::Console
+pub struct Script<'a, R> {
+    comment_prefix: Option<String>,
+    on_error: OnError,
+    err_prefix: String,
+    ws_chars: Matcher<'a, str, char>,
+    nl_chars: Matcher<'a, str, char>,
+    strand: PhantomData<fn() -> R>,
+}
+impl<'a, R> Default for Script<'a, R> {
+    fn default() -> Self {
+        Self {
+            comment_prefix: Some("#".to_string()),
+            on_error: OnError::Stop,
+            err_prefix: "!".to_string(),
+            ws_chars: Matcher::Single(MatcherSingle::Item(' ')),
+            nl_chars: Matcher::Single(MatcherSingle::Item(';')),
+            strand: PhantomData,
+        }
+    }
+}
+impl<'a, R> Script<'a, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prefix of the lines that are skipped as comments, ignoring leading whitespace
+    pub fn comment_prefix(mut self, comment_prefix: impl Into<String>) -> Self {
+        self.comment_prefix = Some(comment_prefix.into());
+        self
+    }
+
+    /// Removes the comment prefix, so that every line that isn't blank is run
+    pub fn no_comments(mut self) -> Self {
+        self.comment_prefix = None;
+        self
+    }
+
+    /// Sets whether the script stops or carries on after a command fails
+    pub fn on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Sets the prefix printed before every error
+    pub fn err_prefix(mut self, err_prefix: impl Into<String>) -> Self {
+        self.err_prefix = err_prefix.into();
+        self
+    }
+
+    /// Sets the characters used to separate the arguments of a command
+    pub fn ws_chars(mut self, ws_chars: Matcher<'a, str, char>) -> Self {
+        self.ws_chars = ws_chars;
+        self
+    }
+
+    /// Sets the characters used to separate the commands on a line
+    pub fn nl_chars(mut self, nl_chars: Matcher<'a, str, char>) -> Self {
+        self.nl_chars = nl_chars;
+        self
+    }
+
+    /// Whether the line is skipped, being blank or a comment
+    fn is_skipped(&self, line: &str) -> bool {
+        let line = line.trim_start();
+
+        line.is_empty()
+            || self
+                .comment_prefix
+                .as_deref()
+                .is_some_and(|prefix| line.starts_with(prefix))
+    }
+}
+impl<'a, R: Strand> Script<'a, R>
+where
+    R::Output: Render,
+    R::Err: Display,
+{
+    /// Reads the script from the file at `path` and runs it
+    pub fn run_file(
+        &self,
+        state: &mut R::State,
+        path: impl AsRef<Path>,
+        output: &mut impl Write,
+    ) -> Result<Report<R::Err>, io::Error> {
+        let script = fs::read_to_string(path)?;
+
+        self.run_str(state, &script, output)
+    }
+
+    /// Runs each line of the script in turn
+    ///
+    /// The output of each command is printed through its [`Render`] implementation, and each
+    /// error is printed tagged with its line and command, the errors also being returned in the
+    /// [`Report`]
+    pub fn run_str(
+        &self,
+        state: &mut R::State,
+        script: &str,
+        output: &mut impl Write,
+    ) -> Result<Report<R::Err>, io::Error> {
+        let mut errors = Vec::new();
+
+        let mut ctx = Context::new(output);
+
+        for (i, line) in script.lines().enumerate() {
+            if self.is_skipped(line) {
+                continue;
+            }
+
+            let commands = split_commands(line, &self.ws_chars, &self.nl_chars);
+
+            for (index, command) in commands.into_iter().enumerate() {
+                match R::run(state, &mut ctx, Input::with_line(line, Some(command)), 1) {
+                    Ok(output) => output.render(&mut ctx)?,
+                    Err(error) => {
+                        let err = ScriptError {
+                            line: i + 1,
+                            command: index + 1,
+                            error,
+                        };
+
+                        writeln!(ctx, "{}{}", self.err_prefix, err)?;
+                        errors.push(err);
+
+                        if self.on_error == OnError::Stop {
+                            return Ok(Report {
+                                errors,
+                                stopped: true,
+                            });
+                        }
+                    }
+                }
+
+                if ctx.exit_requested() {
+                    return Ok(Report {
+                        errors,
+                        stopped: true,
+                    });
+                }
+            }
+        }
+
+        Ok(Report {
+            errors,
+            stopped: false,
+        })
+    }
+}
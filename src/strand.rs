@@ -1,6 +1,4 @@
-use parsr::parser::trimmed::Trimmed;
-
-use crate::error::Error;
+use crate::{context::Context, error::Error, help::Help, input::Input};
 
 /// A Strand is an object that acts on an input, primarily in a console setting
 ///
@@ -12,37 +10,43 @@ use crate::error::Error;
 ///
 /// # Run
 ///
-/// A Strand is given a mutable reference to the current state, a reference to an input string to
-/// parse, a reference to a matcher of whitespace characters, and an index of the current scope
+/// A Strand is given a mutable reference to the current state, the [`Context`] of the console
+/// running it, the [`Input`] left to parse, and an index of the current scope
+///
+/// On success it returns its [`Output`](Strand::Output), which the console prints through
+/// [`Render`](crate::Render), and which is handed straight back to code calling `run` itself
 ///
-/// ```rust
+/// ```ignore
 /// fn run(
 ///     state: &mut Self::State,
-///     input: Option<Trimmed<str>>,
+///     ctx: &mut Context<'_>,
+///     input: Input<'_>,
 ///     index: usize,
-/// ) -> Result<(), Error<Self::Err>>;
+/// ) -> Result<Self::Output, Error<Self::Err>>;
 /// ```
 ///
 /// # Examples
 ///
 /// ```
-/// use parsr::parser_matcher::Matcher;
+/// use std::io::Write;
 ///
-/// use roped::{Strand, Error, EmptyState, Matcher};
+/// use roped::{Context, EmptyState, Error, Input, Strand};
 ///
 /// struct StrandExample;
 ///
 /// impl Strand for StrandExample {
 ///     type State = EmptyState;
+///     type Output = ();
 ///     type Err = ();
 ///
 ///     fn run(
 ///         _state: &mut Self::State,
-///         input: Option<Trimmed<str>>,
+///         ctx: &mut Context<'_>,
+///         mut input: Input<'_>,
 ///         _index: usize,
-///     ) -> Result<(), Error<Self::Err>> {
-///         for command in input.parse_all(ws) {
-///             println!("{},", command.get_arg());
+///     ) -> Result<Self::Output, Error<Self::Err>> {
+///         while let Ok(Some(arg)) = input.next_arg() {
+///             writeln!(ctx, "{},", arg).map_err(|_| Error::Err(()))?;
 ///         }
 ///         Ok(())
 ///     }
@@ -58,24 +62,36 @@ use crate::error::Error;
 pub trait Strand {
     /// The type of the current state.
     type State: ?Sized;
+    /// The type of value the Strand returns when it succeeds.
+    type Output;
     /// The type of error the Strand can return.
     type Err;
 
     /// The function that will be called by the console application to execute the Strand.
     ///
-    /// A Strand is given a mutable reference to the current state, a reference to an input string to
-    /// parse, a reference to a matcher of whitespace characters, and an index of the current scope.
+    /// A Strand is given a mutable reference to the current state, the [`Context`] of the
+    /// console running it, the [`Input`] left to parse, and an index of the current scope.
     ///
-    /// ```
+    /// ```ignore
     /// fn run(
     ///     state: &mut Self::State,
-    ///     input: Option<Trimmed<str>>,
+    ///     ctx: &mut Context<'_>,
+    ///     input: Input<'_>,
     ///     index: usize,
-    /// ) -> Result<(), Error<Self::Err>>;
+    /// ) -> Result<Self::Output, Error<Self::Err>>;
     /// ```
     fn run(
         state: &mut Self::State,
-        input: Option<Trimmed<str>>,
+        ctx: &mut Context<'_>,
+        input: Input<'_>,
         index: usize,
-    ) -> Result<(), Error<Self::Err>>;
+    ) -> Result<Self::Output, Error<Self::Err>>;
+
+    /// Describes how the Strand is used, so that help can be printed for it
+    ///
+    /// Strands constructed using the [`#[derive(Strand)]`](trait@Strand) macro generate this
+    /// from their fields and variants, otherwise nothing is known about the Strand.
+    fn help() -> Help {
+        Help::Unknown
+    }
 }
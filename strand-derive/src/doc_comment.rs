@@ -0,0 +1,46 @@
+use proc_macro2::TokenStream;
+use syn::{Attribute, Expr, ExprLit, Lit, Meta};
+
+/// Collects the first paragraph of the doc comments in `attrs` into a single line
+pub fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for attr in attrs {
+        let value = match &attr.meta {
+            Meta::NameValue(nv) if nv.path.is_ident("doc") => &nv.value,
+            _ => continue,
+        };
+
+        let line = match value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => lit.value(),
+            _ => continue,
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" "))
+    }
+}
+
+/// Constructs an `Option<&'static str>` holding the doc comment in `attrs`, if there is one
+pub fn construct_description(attrs: &[Attribute]) -> TokenStream {
+    match doc_comment(attrs) {
+        Some(doc) => quote::quote!(Some(#doc)),
+        None => quote::quote!(None),
+    }
+}
@@ -1,4 +1,5 @@
 mod build_error;
+mod doc_comment;
 mod meta_map;
 mod search_meta;
 mod strand_enum;
@@ -9,6 +10,24 @@ use strand_struct::strand_derive_struct;
 
 use proc_macro::TokenStream;
 
+/// Derives `Strand` for a struct, parsing its fields from the input, or for an enum, running the
+/// Strand of one of its variants
+///
+/// A unit variant, or one with fields, is parsed inline as if it were a struct, as is a variant
+/// holding a single field that has a `handler = <fn>`. The parsed variant is run by the
+/// `Command` of the enum, or by its handler if it has one, which is called with the variant, the
+/// state and the context, as the handler of a struct is. Either returns the Output of the enum or
+/// an error that converts into its error with `From`
+///
+/// The arguments given to a struct are parsed in order:
+/// - If the struct has flags, an argument beginning with `--`, or with `-` followed by anything
+///   but a digit, is a flag. Flags may be given anywhere among the other arguments
+/// - Any other argument fills the next positional field, then the next field with a default, and
+///   is then collected by the `many` field if there is one
+/// - Once all of those are filled, the rest of the input is the trail, exactly as it was written
+/// - If the struct has flags, an argument of `--` ends flag parsing, so every argument after it
+///   is parsed as if it weren't a flag. It doesn't begin the trail, so fields with defaults are
+///   still filled first. Without flags `--` is an argument like any other
 #[proc_macro_derive(Strand, attributes(strand))]
 pub fn strand_derive(input: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
@@ -20,7 +39,7 @@ pub fn strand_derive(input: TokenStream) -> TokenStream {
         syn::Data::Enum(_) => strand_derive_enum(input)
             .unwrap_or_else(syn::Error::into_compile_error)
             .into(),
-        syn::Data::Union(_) => syn::Error::new_spanned(input, "expected list, \"#[strand(..)]\"")
+        syn::Data::Union(_) => syn::Error::new_spanned(input, "expected a struct or an enum")
             .to_compile_error()
             .into(),
     }
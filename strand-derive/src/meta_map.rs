@@ -33,3 +33,14 @@ where
 
     Ok(map)
 }
+
+/// Parses a single meta, also accepting the `async` keyword as a path
+pub fn parse_meta(input: syn::parse::ParseStream) -> syn::Result<Meta> {
+    if input.peek(syn::Token![async]) {
+        let keyword: syn::Token![async] = input.parse()?;
+        let ident = syn::Ident::new("async", keyword.span);
+        return Ok(Meta::Path(ident.into()));
+    }
+
+    input.parse()
+}
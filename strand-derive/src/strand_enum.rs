@@ -2,32 +2,92 @@ use proc_macro2::TokenStream;
 use quote::ToTokens;
 use syn::{Meta, Type};
 
-use crate::{meta_map::collect_meta_map, search_meta::search_meta};
+use crate::{
+    doc_comment::construct_description,
+    meta_map::{collect_meta_map, parse_meta},
+    search_meta::search_meta,
+    strand_struct::{self, get_fields, Fields},
+};
 
 pub fn strand_derive_enum(input: syn::DeriveInput) -> syn::Result<TokenStream> {
     let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
     let config = get_config(&input)?;
 
-    let (prefixes, names, other) = get_variants(&input)?;
+    let Variants {
+        prefixes,
+        names,
+        other,
+        entries,
+    } = get_variants(&input, config.case_insensitive)?;
+
+    let Config {
+        state,
+        output,
+        error,
+        is_async,
+        case_insensitive,
+    } = config;
+
+    let help = construct_help(&entries, is_async, case_insensitive);
+
+    let complete = construct_complete(&entries, case_insensitive);
+
+    let captures = construct_internal(prefixes, names, other, is_async, case_insensitive);
+
+    let strand = match is_async {
+        false => quote::quote! {
+            impl #impl_generics ::roped::strand::Strand for #name #ty_generics #where_clause {
+                type State = #state;
+                type Output = #output;
+                type Err = #error;
+
+                fn run(
+                    __roped_state: &mut Self::State,
+                    __roped_ctx: &mut ::roped::context::Context<'_>,
+                    __roped_raw_input: ::roped::input::Input<'_>,
+                    __roped_index: usize,
+                ) -> Result<Self::Output, ::roped::error::Error<Self::Err>> {
+                    #captures
+                }
 
-    let captures = construct_internal(prefixes, names, other);
+                fn help() -> ::roped::help::Help {
+                    #help
+                }
+            }
 
-    let Config { state, error } = config;
+            impl #impl_generics ::roped::complete::Complete for #name #ty_generics #where_clause {
+                fn complete(args: &[&str], partial: &str) -> Vec<String> {
+                    #complete
+                }
+            }
+        },
+        // Completion is only generated for a Strand
+        true => quote::quote! {
+            impl #impl_generics ::roped::asynchronous::AsyncStrand for #name #ty_generics #where_clause {
+                type State = #state;
+                type Output = #output;
+                type Err = #error;
+
+                async fn run(
+                    __roped_state: &mut Self::State,
+                    __roped_ctx: &mut ::roped::context::Context<'_>,
+                    __roped_raw_input: ::roped::input::Input<'_>,
+                    __roped_index: usize,
+                ) -> Result<Self::Output, ::roped::error::Error<Self::Err>> {
+                    #captures
+                }
 
-    let gen = quote::quote! {
-        impl ::roped::strand::Strand for #name {
-            type State = #state;
-            type Err = #error;
-
-            fn run(
-                state: &mut Self::State,
-                raw_input: Option<::roped::parsr::parser::trimmed::Trimmed<str>>,
-                index: usize,
-            ) -> Result<(), ::roped::error::Error<Self::Err>> {
-                #captures
+                fn help() -> ::roped::help::Help {
+                    #help
+                }
             }
-        }
+        },
+    };
+
+    let gen = quote::quote! {
+        #strand
     };
 
     Ok(gen)
@@ -36,15 +96,23 @@ pub fn strand_derive_enum(input: syn::DeriveInput) -> syn::Result<TokenStream> {
 #[derive(Clone)]
 pub struct Config {
     pub state: Type,
+    /// The Output shared by every variant
+    pub output: Type,
     pub error: Type,
+    /// Whether an AsyncStrand is generated instead of a Strand
+    pub is_async: bool,
+    /// Whether names and aliases are matched ignoring case
+    pub case_insensitive: bool,
 }
 
 pub fn get_config(input: &syn::DeriveInput) -> syn::Result<Config> {
     if let Some(strand_meta) = search_meta(input.attrs.iter().map(|s| &s.meta), "strand") {
         let meta_list = match strand_meta {
-            syn::Meta::List(v) => v.parse_args_with(
-                syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated,
-            )?,
+            syn::Meta::List(v) => v.parse_args_with(|input: syn::parse::ParseStream| {
+                syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated_with(
+                    input, parse_meta,
+                )
+            })?,
             _ => {
                 return Err(syn::Error::new_spanned(
                     strand_meta,
@@ -53,7 +121,17 @@ pub fn get_config(input: &syn::DeriveInput) -> syn::Result<Config> {
             }
         };
 
-        let meta_map = collect_meta_map(meta_list, &["state", "input", "error"])?;
+        let meta_map = collect_meta_map(
+            meta_list,
+            &[
+                "state",
+                "output",
+                "input",
+                "error",
+                "async",
+                "case_insensitive",
+            ],
+        )?;
 
         let state: Type = match meta_map.get("state") {
             Some(m) => match m {
@@ -68,6 +146,19 @@ pub fn get_config(input: &syn::DeriveInput) -> syn::Result<Config> {
             None => syn::parse_quote! { roped::base_types::EmptyState },
         };
 
+        let output: Type = match meta_map.get("output") {
+            Some(m) => match m {
+                Meta::NameValue(n) => syn::parse(n.value.to_token_stream().into())?,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        "expected type, \"output = <type>\"",
+                    ))
+                }
+            },
+            None => syn::parse_quote! { () },
+        };
+
         let error: Type = match meta_map.get("error") {
             Some(m) => match m {
                 Meta::NameValue(n) => syn::parse(n.value.to_token_stream().into())?,
@@ -81,44 +172,92 @@ pub fn get_config(input: &syn::DeriveInput) -> syn::Result<Config> {
             None => syn::parse_quote! { String },
         };
 
-        Ok(Config { state, error })
+        let is_async = match meta_map.get("async") {
+            Some(Meta::Path(_)) => true,
+            Some(m) => return Err(syn::Error::new_spanned(m, "expected \"async\"")),
+            None => false,
+        };
+
+        let case_insensitive = match meta_map.get("case_insensitive") {
+            Some(Meta::Path(_)) => true,
+            Some(m) => return Err(syn::Error::new_spanned(m, "expected \"case_insensitive\"")),
+            None => false,
+        };
+
+        Ok(Config {
+            state,
+            output,
+            error,
+            is_async,
+            case_insensitive,
+        })
     } else {
         Ok(Config {
             state: syn::parse_quote! { roped::base_types::EmptyState },
+            output: syn::parse_quote! { () },
             error: syn::parse_quote! { String },
+            is_async: false,
+            case_insensitive: false,
         })
     }
 }
 
 #[derive(Clone)]
-struct Prefix<'a>(String, &'a Type);
+struct Prefix<'a>(String, Target<'a>);
+#[derive(Clone)]
+struct Name<'a>(String, Target<'a>);
 #[derive(Clone)]
-struct Name<'a>(String, &'a Type);
-#[derive(Clone, Copy)]
-struct Other<'a>(&'a Type);
+struct Other<'a>(Target<'a>);
 
-fn get_variants(input: &syn::DeriveInput) -> syn::Result<(Vec<Prefix>, Vec<Name>, Option<Other>)> {
+/// What a variant runs when it is reached
+#[derive(Clone)]
+enum Target<'a> {
+    /// The Strand held by a variant, `<name>(<type>)`
+    Strand(&'a Type),
+    /// A unit variant, or one with fields, parsed inline
+    Inline(Box<Inline<'a>>),
+}
+
+/// A variant parsed inline, run by its handler or otherwise the Command of the enum
+#[derive(Clone)]
+struct Inline<'a> {
+    ident: &'a syn::Ident,
+    fields: Fields<'a>,
+    /// `handler = <fn>`, run with the parsed variant in place of the Command of the enum
+    handler: Option<syn::ExprPath>,
+}
+
+#[derive(Clone)]
+struct HelpEntry<'a> {
+    kind: proc_macro2::TokenStream,
+    target: Target<'a>,
+    attrs: &'a [syn::Attribute],
+}
+
+struct Variants<'a> {
+    prefixes: Vec<Prefix<'a>>,
+    names: Vec<Name<'a>>,
+    other: Option<Other<'a>>,
+    entries: Vec<HelpEntry<'a>>,
+}
+
+fn get_variants(input: &syn::DeriveInput, case_insensitive: bool) -> syn::Result<Variants<'_>> {
     let mut prefixes: Vec<Prefix> = Vec::new();
     let mut names: Vec<Name> = Vec::new();
 
+    // Every name, alias and prefix so far, lowercased if they are matched ignoring case
+    let mut taken: Vec<String> = Vec::new();
+
     let mut other: Option<Other> = None;
 
+    let mut entries: Vec<HelpEntry> = Vec::new();
+
     let data = match &input.data {
         syn::Data::Enum(v) => v,
-        _ => panic!("internal error, this should not happen"),
+        _ => return Err(syn::Error::new_spanned(input, "expected an enum")),
     };
 
     for variant in &data.variants {
-        let variant_type = match &variant.fields {
-            syn::Fields::Unnamed(v) if v.unnamed.len() == 1 => &v.unnamed[0].ty,
-            _ => {
-                return Err(syn::Error::new_spanned(
-                    variant,
-                    "expected tuple, \"<name>(<type>)\"",
-                ))
-            }
-        };
-
         let strand_meta = search_meta(variant.attrs.iter().map(|s| &s.meta), "strand").ok_or(
             syn::Error::new_spanned(variant, "expected attribute, \"#[strand(..)]\""),
         )?;
@@ -135,7 +274,47 @@ fn get_variants(input: &syn::DeriveInput) -> syn::Result<(Vec<Prefix>, Vec<Name>
             }
         };
 
-        let meta_map = collect_meta_map(meta_list, &["name", "prefix", "other"])?;
+        // Aliases can be given more than once, so are taken out before the rest are collected
+        let (aliases, meta_list): (Vec<Meta>, Vec<Meta>) = meta_list
+            .into_iter()
+            .partition(|meta| meta.path().is_ident("alias"));
+        let meta_list: syn::punctuated::Punctuated<Meta, syn::Token![,]> =
+            meta_list.into_iter().collect();
+
+        let meta_map = collect_meta_map(meta_list, &["name", "prefix", "other", "handler"])?;
+
+        let handler = match meta_map.get("handler") {
+            Some(Meta::NameValue(syn::MetaNameValue {
+                value: syn::Expr::Path(path),
+                ..
+            })) => Some(path.clone()),
+            Some(m) => {
+                return Err(syn::Error::new_spanned(
+                    m,
+                    "expected function, \"handler = <fn>\"",
+                ))
+            }
+            None => None,
+        };
+
+        // A single field is only parsed inline when the variant has a handler to run it
+        let target = match (&variant.fields, handler) {
+            (syn::Fields::Unnamed(v), None) if v.unnamed.len() == 1 => {
+                Target::Strand(&v.unnamed[0].ty)
+            }
+            (fields, handler) => Target::Inline(Box::new(Inline {
+                ident: &variant.ident,
+                fields: get_fields(fields)?,
+                handler,
+            })),
+        };
+
+        if let (Some(alias), None) = (aliases.first(), meta_map.get("name")) {
+            return Err(syn::Error::new_spanned(
+                alias,
+                "an alias is only supported with a name, \"name = <string>, alias = <string>\"",
+            ));
+        }
 
         let mut no_reference = false;
 
@@ -154,19 +333,41 @@ fn get_variants(input: &syn::DeriveInput) -> syn::Result<(Vec<Prefix>, Vec<Name>
                 }
             };
 
-            if prefixes.iter().any(|Prefix(s, _)| s == &string) {
-                return Err(syn::Error::new_spanned(meta, "prefix/name already exists"));
-            }
+            take(&mut taken, &string, meta, case_insensitive)?;
 
-            if names.iter().any(|Name(s, _)| s == &string) {
-                return Err(syn::Error::new_spanned(meta, "prefix/name already exists"));
-            }
+            entries.push(HelpEntry {
+                kind: quote::quote!(::roped::help::EntryKind::Name(#string)),
+                target: target.clone(),
+                attrs: &variant.attrs,
+            });
 
-            if string.is_empty() {
-                return Err(syn::Error::new_spanned(meta, "expected non-empty string"));
-            }
+            names.push(Name(string, target.clone()));
+
+            for meta in &aliases {
+                let string: String = match meta {
+                    Meta::NameValue(nv) => {
+                        let temp: proc_macro::TokenStream = nv.value.to_token_stream().into();
+                        let lit: syn::LitStr = syn::parse(temp)?;
+                        lit.value()
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            meta,
+                            "expected string \"<attr> = <string>\"",
+                        ))
+                    }
+                };
 
-            names.push(Name(string, variant_type));
+                take(&mut taken, &string, meta, case_insensitive)?;
+
+                entries.push(HelpEntry {
+                    kind: quote::quote!(::roped::help::EntryKind::Alias(#string)),
+                    target: target.clone(),
+                    attrs: &variant.attrs,
+                });
+
+                names.push(Name(string, target.clone()));
+            }
 
             no_reference = true;
         }
@@ -186,19 +387,16 @@ fn get_variants(input: &syn::DeriveInput) -> syn::Result<(Vec<Prefix>, Vec<Name>
                 }
             };
 
-            if prefixes.iter().any(|Prefix(s, _)| s == &string) {
-                return Err(syn::Error::new_spanned(meta, "prefix/name already exists"));
-            }
-
-            if names.iter().any(|Name(s, _)| s == &string) {
-                return Err(syn::Error::new_spanned(meta, "prefix/name already exists"));
-            }
+            // Prefixes are always matched exactly
+            take(&mut taken, &string, meta, false)?;
 
-            if string.is_empty() {
-                return Err(syn::Error::new_spanned(meta, "expected non-empty string"));
-            }
+            entries.push(HelpEntry {
+                kind: quote::quote!(::roped::help::EntryKind::Prefix(#string)),
+                target: target.clone(),
+                attrs: &variant.attrs,
+            });
 
-            prefixes.push(Prefix(string, variant_type));
+            prefixes.push(Prefix(string, target.clone()));
 
             no_reference = true;
         }
@@ -207,7 +405,13 @@ fn get_variants(input: &syn::DeriveInput) -> syn::Result<(Vec<Prefix>, Vec<Name>
             match meta {
                 Meta::Path(_) => {
                     if other.is_none() {
-                        other = Some(Other(variant_type))
+                        entries.push(HelpEntry {
+                            kind: quote::quote!(::roped::help::EntryKind::Other),
+                            target: target.clone(),
+                            attrs: &variant.attrs,
+                        });
+
+                        other = Some(Other(target.clone()))
                     } else {
                         return Err(syn::Error::new_spanned(meta, "\"other\" already exists"));
                     }
@@ -226,46 +430,193 @@ fn get_variants(input: &syn::DeriveInput) -> syn::Result<(Vec<Prefix>, Vec<Name>
         }
     }
 
-    Ok((prefixes, names, other))
+    Ok(Variants {
+        prefixes,
+        names,
+        other,
+        entries,
+    })
+}
+
+/// Checks that a name, alias or prefix is neither empty nor already taken, then takes it
+fn take(
+    taken: &mut Vec<String>,
+    string: &str,
+    meta: &Meta,
+    case_insensitive: bool,
+) -> syn::Result<()> {
+    if string.is_empty() {
+        return Err(syn::Error::new_spanned(meta, "expected non-empty string"));
+    }
+
+    let string = match case_insensitive {
+        false => string.to_string(),
+        true => string.to_lowercase(),
+    };
+
+    if taken.contains(&string) {
+        return Err(syn::Error::new_spanned(
+            meta,
+            "prefix/name/alias already exists",
+        ));
+    }
+
+    taken.push(string);
+
+    Ok(())
+}
+
+fn construct_help(
+    entries: &[HelpEntry],
+    is_async: bool,
+    case_insensitive: bool,
+) -> proc_macro2::TokenStream {
+    let strand = match is_async {
+        false => quote::quote!(::roped::strand::Strand),
+        true => quote::quote!(::roped::asynchronous::AsyncStrand),
+    };
+
+    let entries: Vec<_> = entries
+        .iter()
+        .map(
+            |HelpEntry {
+                 kind,
+                 target,
+                 attrs,
+             }| {
+                let description = construct_description(attrs);
+                let help = match target {
+                    Target::Strand(ty) => quote::quote!(<#ty as #strand>::help),
+                    Target::Inline(inline) => {
+                        let help = strand_struct::construct_help(attrs, &inline.fields);
+                        quote::quote!(|| #help)
+                    }
+                };
+
+                quote::quote! {
+                    ::roped::help::Entry {
+                        kind: #kind,
+                        description: #description,
+                        help: #help,
+                    }
+                }
+            },
+        )
+        .collect();
+
+    quote::quote! {
+        ::roped::help::Help::Scope {
+            entries: vec![#(#entries),*],
+            case_insensitive: #case_insensitive,
+        }
+    }
+}
+
+fn construct_complete(entries: &[HelpEntry], case_insensitive: bool) -> proc_macro2::TokenStream {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(
+            |HelpEntry {
+                 kind,
+                 target,
+                 attrs,
+             }| {
+                // Completing from the help of a nested Strand leaves it free of a Complete bound
+                let complete = match target {
+                    Target::Strand(ty) => quote::quote! {
+                        |args: &[&str], partial: &str| {
+                            ::roped::complete::complete_help(
+                                &<#ty as ::roped::strand::Strand>::help(),
+                                args,
+                                partial,
+                            )
+                        }
+                    },
+                    Target::Inline(inline) => {
+                        let help = strand_struct::construct_help(attrs, &inline.fields);
+                        quote::quote! {
+                            |args: &[&str], partial: &str| {
+                                ::roped::complete::complete_help(&#help, args, partial)
+                            }
+                        }
+                    }
+                };
+
+                quote::quote! {
+                    ::roped::complete::ScopeEntry {
+                        kind: #kind,
+                        complete: #complete,
+                    }
+                }
+            },
+        )
+        .collect();
+
+    quote::quote! {
+        ::roped::complete::complete_scope(&[#(#entries),*], #case_insensitive, args, partial)
+    }
 }
 
 fn construct_internal(
     prefixes: Vec<Prefix>,
     names: Vec<Name>,
     other: Option<Other>,
+    is_async: bool,
+    case_insensitive: bool,
 ) -> proc_macro2::TokenStream {
-    let prefix_quote = prefix_matchers(prefixes);
-    let name_quote = name_matchers(names);
-    let other_quote = other_matcher(other);
-    let no_input = no_input(other);
+    // The names and aliases are suggested when the scope doesn't exist
+    let candidates: Vec<&str> = names.iter().map(|Name(s, _)| s.as_str()).collect();
+    let other_quote = other_matcher(other.as_ref(), &candidates, is_async);
+
+    let prefix_quote = prefix_matchers(prefixes, is_async);
+    let name_quote = name_matchers(names, is_async, case_insensitive);
+    let no_input = no_input(other.as_ref(), is_async);
+
+    // The span of the scope is only needed for the errors of a scope without an other
+    let span_binding = match &other {
+        Some(_) => quote::quote!(_),
+        None => quote::quote!(__roped_arg_span),
+    };
+
+    let scope = match case_insensitive {
+        false => quote::quote!(&*__roped_arg),
+        true => quote::quote!(&*__roped_arg.to_lowercase()),
+    };
 
     quote::quote!(
-        if let Some(input) = raw_input {
-            if false { unsafe { std::hint::unreachable_unchecked() } }
-            #prefix_quote
-            else {
-                let parse_pair = input.parse_once();
+        let mut __roped_input = __roped_raw_input;
 
-                match parse_pair.arg.get_internal() {
+        if false { unsafe { std::hint::unreachable_unchecked() } }
+        #prefix_quote
+        else {
+            match __roped_input.next_arg_spanned() {
+                Ok(Some((__roped_arg, #span_binding))) => match #scope {
                     #name_quote
                     #other_quote
-                }
+                },
+                Ok(None) => #no_input,
+                Err(__roped_variant) => Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(__roped_index, __roped_variant).with_span(__roped_input.rest_span()),
+                ))),
             }
-        } else {
-            #no_input
         }
     )
 }
 
-fn prefix_matchers(prefixes: Vec<Prefix>) -> proc_macro2::TokenStream {
+fn prefix_matchers(prefixes: Vec<Prefix>, is_async: bool) -> proc_macro2::TokenStream {
     let matchers: Vec<_> = prefixes
         .into_iter()
         .map(|Prefix(s, t)| {
+            let run = run_strand(
+                &t,
+                quote::quote!(__roped_trail),
+                quote::quote!(__roped_index),
+                is_async,
+            );
+
             quote::quote! {
-                else if let Some(trail) = ::roped::parsr::parser::trim::Trim::trim_once(
-                    input.get_internal(), ::roped::parsr::parser_matcher::Matcher::ident(&#s)
-                ) {
-                    #t::run(state, ::roped::parsr::parser::trimmed::Trimmed::<str>::new(trail, input.get_matcher()), index)
+                else if let Some(__roped_trail) = __roped_raw_input.strip_prefix(#s) {
+                    #run
                 }
             }
         })
@@ -276,12 +627,27 @@ fn prefix_matchers(prefixes: Vec<Prefix>) -> proc_macro2::TokenStream {
     }
 }
 
-fn name_matchers(names: Vec<Name>) -> proc_macro2::TokenStream {
+fn name_matchers(
+    names: Vec<Name>,
+    is_async: bool,
+    case_insensitive: bool,
+) -> proc_macro2::TokenStream {
     let matchers: Vec<_> = names
         .into_iter()
         .map(|Name(s, t)| {
+            let s = match case_insensitive {
+                false => s,
+                true => s.to_lowercase(),
+            };
+            let run = run_strand(
+                &t,
+                quote::quote!(__roped_input),
+                quote::quote!(__roped_index + 1),
+                is_async,
+            );
+
             quote::quote! {
-                #s => #t::run(state, parse_pair.trail, index + 1),
+                #s => #run,
             }
         })
         .collect();
@@ -291,45 +657,131 @@ fn name_matchers(names: Vec<Name>) -> proc_macro2::TokenStream {
     )
 }
 
-fn other_matcher(other: Option<Other>) -> proc_macro2::TokenStream {
+fn other_matcher(
+    other: Option<&Other>,
+    candidates: &[&str],
+    is_async: bool,
+) -> proc_macro2::TokenStream {
     match other {
         Some(Other(t)) => {
+            let run = run_strand(
+                t,
+                quote::quote!(__roped_raw_input),
+                quote::quote!(__roped_index),
+                is_async,
+            );
+
             quote::quote! {
-                _ => #t::run(state, raw_input, index),
+                _ => #run,
             }
         }
         None => {
             quote::quote! {
-                "" => Err(::roped::error::Error::Internal(::roped::error::InternalError{
-                    index,
-                    variant: ::roped::error::ErrorType::Expected(::roped::error::ArgType::Scope)
-                })),
-                f => Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                    index,
-                    variant: ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
-                        arg: f.to_string(),
-                        parse_type: ::roped::error::ArgType::Scope,
-                    })
-                })),
+                "" => Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(
+                        __roped_index,
+                        ::roped::error::ErrorType::Expected(::roped::error::ArgType::Scope),
+                    )
+                    .with_span(__roped_arg_span),
+                ))),
+                _ => Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(
+                        __roped_index,
+                        ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
+                            arg: __roped_arg.to_string(),
+                            parse_type: ::roped::error::ArgType::Scope,
+                            reason: None,
+                        }),
+                    )
+                    .with_span(__roped_arg_span)
+                    .with_suggestions(::roped::error::suggestions(&__roped_arg, &[#(#candidates),*])),
+                ))),
             }
         }
     }
 }
 
-fn no_input(other: Option<Other>) -> proc_macro2::TokenStream {
+fn no_input(other: Option<&Other>, is_async: bool) -> proc_macro2::TokenStream {
     match other {
-        Some(Other(t)) => {
+        Some(Other(t)) => run_strand(
+            t,
+            quote::quote!(__roped_raw_input),
+            quote::quote!(__roped_index),
+            is_async,
+        ),
+        None => {
             quote::quote! {
-                #t::run(state, None, index)
+                Err(::roped::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(
+                        __roped_index,
+                        ::roped::error::ErrorType::Expected(::roped::error::ArgType::Scope),
+                    )
+                    .with_span(__roped_input.rest_span()),
+                )))
             }
         }
-        None => {
-            quote::quote! {
-                Err(::roped::Error::Internal(::roped::error::InternalError{
-                    index,
-                    variant: ::roped::error::ErrorType::Expected(::roped::error::ArgType::Scope)
-                }))
+    }
+}
+
+/// Runs the Strand of a variant, awaiting it if the enum is async, its error being converted into
+/// that of the enum with `From`
+///
+/// A variant parsed inline is constructed and run by its handler if it has one, otherwise by the
+/// Command of the enum, or its AsyncCommand if async
+fn run_strand(
+    target: &Target,
+    input: proc_macro2::TokenStream,
+    index: proc_macro2::TokenStream,
+    is_async: bool,
+) -> proc_macro2::TokenStream {
+    let inline = match (target, is_async) {
+        (Target::Strand(ty), false) => {
+            return quote::quote! {
+                <#ty as ::roped::strand::Strand>::run(__roped_state, __roped_ctx, #input, #index)
+                    .map_err(|__roped_err| __roped_err.map(::std::convert::From::from))
+            }
+        }
+        (Target::Strand(ty), true) => {
+            return quote::quote! {
+                <#ty as ::roped::asynchronous::AsyncStrand>::run(__roped_state, __roped_ctx, #input, #index)
+                    .await
+                    .map_err(|__roped_err| __roped_err.map(::std::convert::From::from))
             }
         }
+        (Target::Inline(inline), _) => inline,
+    };
+
+    let internal = strand_struct::construct_internal(&inline.fields);
+
+    let ident = inline.ident;
+    let constructor =
+        strand_struct::construct_constructor(&inline.fields, quote::quote!(Self::#ident));
+
+    let action = match (&inline.handler, is_async) {
+        (Some(handler), false) => {
+            quote::quote!((#handler)(__roped_this, __roped_state, __roped_ctx))
+        }
+        (Some(handler), true) => {
+            quote::quote!((#handler)(__roped_this, __roped_state, __roped_ctx).await)
+        }
+        (None, false) => quote::quote! {
+            <Self as ::roped::command::Command>::action(__roped_this, __roped_state, __roped_ctx)
+        },
+        (None, true) => quote::quote! {
+            <Self as ::roped::asynchronous::AsyncCommand>::action(__roped_this, __roped_state, __roped_ctx).await
+        },
+    };
+
+    quote::quote! {
+        {
+            let mut __roped_input = #input;
+            let mut __roped_index = #index;
+
+            #internal
+
+            let __roped_this = #constructor;
+
+            #action.map_err(|__roped_err| ::roped::error::Error::Err(::std::convert::From::from(__roped_err)))
+        }
     }
 }
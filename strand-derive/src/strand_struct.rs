@@ -1,51 +1,281 @@
+use std::collections::HashMap;
+
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::Type;
 
-use crate::{meta_map::collect_meta_map, search_meta::search_meta};
+use crate::{
+    doc_comment::construct_description,
+    meta_map::{collect_meta_map, parse_meta},
+    search_meta::search_meta,
+};
 
 pub fn strand_derive_struct(input: syn::DeriveInput) -> syn::Result<TokenStream> {
     let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let config = get_config(&input)?;
+
+    let data = match &input.data {
+        syn::Data::Struct(v) => v,
+        _ => return Err(syn::Error::new_spanned(&input, "expected a struct")),
+    };
 
-    let (fields, extras) = get_fields(&input)?;
+    let fields = get_fields(&data.fields)?;
 
-    let internal = construct_internal(fields, extras);
+    let help = construct_help(&input.attrs, &fields);
 
-    let gen = quote::quote! {
-        impl ::roped::strand::Strand for #name {
-            type State = <Self as ::roped::command::Command>::State;
-            type Err = <Self as ::roped::command::Command>::Err;
+    let internal = construct_internal(&fields);
+    let constructor = construct_constructor(&fields, quote!(Self));
 
-            fn run(
-                state: &mut Self::State,
-                raw_input: Option<::roped::parsr::parser::trimmed::Trimmed<str>>,
-                index: usize,
-            ) -> Result<(), ::roped::error::Error<Self::Err>> {
-                let mut input = raw_input;
-                let mut index = index;
+    let command = match config.is_async {
+        false => quote!(::roped::command::Command),
+        true => quote!(::roped::asynchronous::AsyncCommand),
+    };
 
-                #internal
+    // A handler is run in place of a Command, the types defaulting as they do for an enum
+    let (state, output, error, action) = match &config.handler {
+        Some(handler) => (
+            type_or(&config.state, quote!(::roped::base_types::EmptyState)),
+            type_or(&config.output, quote!(())),
+            type_or(&config.error, quote!(String)),
+            quote!((#handler)(__roped_this, __roped_state, __roped_ctx)),
+        ),
+        None => (
+            type_or(&config.state, quote!(<Self as #command>::State)),
+            type_or(&config.output, quote!(<Self as #command>::Output)),
+            type_or(&config.error, quote!(<Self as #command>::Err)),
+            quote!(<Self as #command>::action(__roped_this, __roped_state, __roped_ctx)),
+        ),
+    };
+
+    let strand = match config.is_async {
+        false => quote::quote! {
+            impl #impl_generics ::roped::strand::Strand for #name #ty_generics #where_clause {
+                type State = #state;
+                type Output = #output;
+                type Err = #error;
+
+                fn run(
+                    __roped_state: &mut Self::State,
+                    __roped_ctx: &mut ::roped::context::Context<'_>,
+                    __roped_raw_input: ::roped::input::Input<'_>,
+                    __roped_index: usize,
+                ) -> Result<Self::Output, ::roped::error::Error<Self::Err>> {
+                    let mut __roped_input = __roped_raw_input;
+                    let mut __roped_index = __roped_index;
+
+                    #internal
+
+                    let __roped_this = #constructor;
+
+                    #action.map_err(|__roped_err| ::roped::error::Error::Err(::std::convert::From::from(__roped_err)))
+                }
 
-                this.action(state).map_err(|err| ::roped::error::Error::Err(err))
+                fn help() -> ::roped::help::Help {
+                    #help
+                }
             }
-        }
+
+            impl #impl_generics ::roped::complete::Complete for #name #ty_generics #where_clause {}
+        },
+        // Completion is only generated for a Strand
+        true => quote::quote! {
+            impl #impl_generics ::roped::asynchronous::AsyncStrand for #name #ty_generics #where_clause {
+                type State = #state;
+                type Output = #output;
+                type Err = #error;
+
+                async fn run(
+                    __roped_state: &mut Self::State,
+                    __roped_ctx: &mut ::roped::context::Context<'_>,
+                    __roped_raw_input: ::roped::input::Input<'_>,
+                    __roped_index: usize,
+                ) -> Result<Self::Output, ::roped::error::Error<Self::Err>> {
+                    let mut __roped_input = __roped_raw_input;
+                    let mut __roped_index = __roped_index;
+
+                    #internal
+
+                    let __roped_this = #constructor;
+
+                    #action
+                        .await
+                        .map_err(|__roped_err| ::roped::error::Error::Err(::std::convert::From::from(__roped_err)))
+                }
+
+                fn help() -> ::roped::help::Help {
+                    #help
+                }
+            }
+        },
+    };
+
+    let gen = quote::quote! {
+        #strand
     };
 
     Ok(gen)
 }
 
-#[derive(Clone, Copy)]
+/// The options given to the struct itself through `#[strand(..)]`
+#[derive(Clone, Default)]
+struct Config {
+    /// Whether an AsyncStrand is generated, running an AsyncCommand instead of a Command
+    is_async: bool,
+    state: Option<Type>,
+    output: Option<Type>,
+    /// The error of the Strand, which the error of its Command is converted into with `From`
+    error: Option<Type>,
+    /// `handler = <fn>`, run with the parsed struct in place of a Command
+    handler: Option<syn::ExprPath>,
+}
+
+fn get_config(input: &syn::DeriveInput) -> syn::Result<Config> {
+    let strand_meta = match search_meta(input.attrs.iter().map(|s| &s.meta), "strand") {
+        Some(v) => v,
+        None => return Ok(Config::default()),
+    };
+
+    let meta_list = match strand_meta {
+        syn::Meta::List(v) => v.parse_args_with(|input: syn::parse::ParseStream| {
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated_with(
+                input, parse_meta,
+            )
+        })?,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                strand_meta,
+                "expected list, \"#[strand(..)]\"",
+            ))
+        }
+    };
+
+    let meta_map = collect_meta_map(meta_list, &["state", "output", "error", "handler", "async"])?;
+
+    let is_async = match meta_map.get("async") {
+        Some(syn::Meta::Path(_)) => true,
+        Some(m) => return Err(syn::Error::new_spanned(m, "expected \"async\"")),
+        None => false,
+    };
+
+    let state = get_type(&meta_map, "state")?;
+    let output = get_type(&meta_map, "output")?;
+    let error = get_type(&meta_map, "error")?;
+
+    let handler = match meta_map.get("handler") {
+        Some(syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Path(path),
+            ..
+        })) => Some(path.clone()),
+        Some(m) => {
+            return Err(syn::Error::new_spanned(
+                m,
+                "expected function, \"handler = <fn>\"",
+            ))
+        }
+        None => None,
+    };
+
+    Ok(Config {
+        is_async,
+        state,
+        output,
+        error,
+        handler,
+    })
+}
+
+fn get_type(meta_map: &HashMap<&str, syn::Meta>, name: &str) -> syn::Result<Option<Type>> {
+    match meta_map.get(name) {
+        Some(syn::Meta::NameValue(nv)) => Ok(Some(syn::parse2(nv.value.to_token_stream())?)),
+        Some(m) => Err(syn::Error::new_spanned(
+            m,
+            format!("expected type, \"{} = <type>\"", name),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// The tokens of the type if it was given, otherwise `default`
+fn type_or(ty: &Option<Type>, default: TokenStream) -> TokenStream {
+    match ty {
+        Some(ty) => ty.to_token_stream(),
+        None => default,
+    }
+}
+
+#[derive(Clone)]
 struct Field<'a> {
-    ident: &'a syn::Ident,
+    ident: syn::Ident,
     ty: &'a Type,
+    attrs: &'a [syn::Attribute],
+    parser: Parser,
+    rules: Rules,
+}
+
+/// How the value of a field is cast from an argument
+#[derive(Clone, Default)]
+enum Parser {
+    /// `FromStr::from_str`
+    #[default]
+    FromStr,
+    /// `parse_with = <fn>`, a function taking the argument
+    With(syn::Expr),
+    /// `arg`, `roped::Arg::parse_arg` taking the argument and the state
+    Arg,
+}
+
+/// The rules a value is checked against once it has been cast
+#[derive(Clone, Default)]
+struct Rules {
+    range: Option<syn::Expr>,
+    choices: Option<Vec<syn::Expr>>,
+    min_len: Option<usize>,
+    validate: Option<syn::ExprPath>,
+}
+impl Rules {
+    fn is_empty(&self) -> bool {
+        self.range.is_none()
+            && self.choices.is_none()
+            && self.min_len.is_none()
+            && self.validate.is_none()
+    }
+}
+
+/// The fields of a strand, by how they are parsed
+///
+/// Positional fields and then those with defaults are filled in order by the arguments that
+/// aren't flags, with the trail taking the rest of the input once they are all filled. See the
+/// derive for the full rules
+#[derive(Clone, Default)]
+pub(crate) struct Fields<'a> {
+    shape: Shape,
+    /// The fields in the order they are declared
+    members: Vec<syn::Ident>,
+    positional: Vec<Field<'a>>,
+    defaults: Vec<DefaultField<'a>>,
+    flags: Vec<Flag<'a>>,
+    many: Option<Many<'a>>,
+    trail: Option<Field<'a>>,
+}
+
+/// The kind of struct the fields belong to
+#[derive(Clone, Copy, Default)]
+enum Shape {
+    #[default]
+    Named,
+    Unnamed,
+    Unit,
 }
 
+/// A `Vec<T>` collecting the arguments left once the positional fields are filled
 #[derive(Clone)]
-enum Extras<'a> {
-    None,
-    Default(Vec<DefaultField<'a>>),
-    Flags(Vec<Flag<'a>>),
-    Trail(Field<'a>),
+struct Many<'a> {
+    field: Field<'a>,
+    item: &'a Type,
+    min: usize,
+    max: Option<usize>,
 }
 
 #[derive(Clone)]
@@ -56,35 +286,64 @@ struct DefaultField<'a> {
 
 #[derive(Clone)]
 struct Flag<'a> {
-    ident: &'a syn::Ident,
+    ident: syn::Ident,
     name: String,
+    short: Option<char>,
     flag_type: FlagType<'a>,
+    /// Whether giving the flag more than once is an error
+    once: bool,
+    fallback: Fallback,
+    attrs: &'a [syn::Attribute],
+    parser: Parser,
+    rules: Rules,
 }
 
 #[derive(Clone, Copy)]
 enum FlagType<'a> {
     Trigger,
     Value(&'a Type),
+    /// A `Vec<T>` collecting the value each time the flag is given
+    Repeated(&'a Type),
+    /// A number counting the times the flag is given
+    Count(&'a Type),
 }
 
-fn get_fields(input: &syn::DeriveInput) -> syn::Result<(Vec<Field>, Extras)> {
-    let mut field_state = true;
+/// What a flag that takes a value is set to when it isn't given
+#[derive(Clone, Default)]
+enum Fallback {
+    /// `None`, the field being an `Option<T>`
+    #[default]
+    None,
+    /// `required`, an error is returned
+    Required,
+    /// `default = <expr>`
+    Default(syn::Expr),
+}
 
-    let mut fields: Vec<Field> = Vec::new();
-    let mut extras: Extras = Extras::None;
+/// Finds how each of the fields of a struct, or of a variant parsed inline, is parsed
+pub(crate) fn get_fields(data_fields: &syn::Fields) -> syn::Result<Fields<'_>> {
+    let shape = match data_fields {
+        syn::Fields::Named(_) => Shape::Named,
+        syn::Fields::Unnamed(_) => Shape::Unnamed,
+        syn::Fields::Unit => Shape::Unit,
+    };
 
-    let data = match &input.data {
-        syn::Data::Struct(v) => v,
-        _ => panic!("internal error, this should not happen"),
+    let mut fields = Fields {
+        shape,
+        ..Fields::default()
     };
 
-    for field in &data.fields {
-        let ident: &syn::Ident = match &field.ident {
-            Some(v) => v,
-            None => panic!("If this happens you must be doing something wrong"),
+    for (i, field) in data_fields.iter().enumerate() {
+        // The fields of a tuple struct are held in variables named after their position
+        let ident = match &field.ident {
+            Some(v) => v.clone(),
+            None => quote::format_ident!("arg{}", i),
         };
 
+        fields.members.push(ident.clone());
+
         let ty = &field.ty;
+        let attrs = field.attrs.as_slice();
 
         if let Some(strand_meta) = search_meta(field.attrs.iter().map(|s| &s.meta), "strand") {
             let meta_list = match strand_meta {
@@ -99,18 +358,72 @@ fn get_fields(input: &syn::DeriveInput) -> syn::Result<(Vec<Field>, Extras)> {
                 }
             };
 
-            let meta_map = collect_meta_map(meta_list, &["default", "flag", "trail"])?;
+            let meta_map = collect_meta_map(
+                meta_list,
+                &[
+                    "default",
+                    "flag",
+                    "short",
+                    "count",
+                    "once",
+                    "required",
+                    "trail",
+                    "many",
+                    "min",
+                    "max",
+                    "parse_with",
+                    "arg",
+                    "range",
+                    "choices",
+                    "min_len",
+                    "validate",
+                ],
+            )?;
+
+            if let (Some(meta), None) = (meta_map.get("short"), meta_map.get("flag")) {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "\"short\" is only supported on flags, \"flag = <name>, short = <char>\"",
+                ));
+            }
 
-            if field_state {
-                if meta_map.is_empty() {
-                    fields.push(Field { ident, ty });
-                    continue;
-                } else {
-                    field_state = false
+            for name in ["count", "once", "required"] {
+                if let (Some(meta), None) = (meta_map.get(name), meta_map.get("flag")) {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        format!("\"{}\" is only supported on flags", name),
+                    ));
                 }
             }
 
-            if let Some(meta) = meta_map.get("default") {
+            let parser = get_parser(&meta_map)?;
+            let rules = get_rules(&meta_map)?;
+
+            if let (Some(meta), None) = (
+                meta_map.get("min").or(meta_map.get("max")),
+                meta_map.get("many"),
+            ) {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "\"min\" and \"max\" are only supported with \"many\"",
+                ));
+            }
+
+            let is_positional = !["default", "flag", "trail", "many"]
+                .iter()
+                .any(|name| meta_map.contains_key(name));
+
+            if is_positional {
+                let positional = Field {
+                    ident,
+                    ty,
+                    attrs,
+                    parser,
+                    rules,
+                };
+
+                fields.positional.push(positional);
+            } else if let (Some(meta), None) = (meta_map.get("default"), meta_map.get("flag")) {
                 let default: syn::Expr = match meta {
                     syn::Meta::NameValue(nv) => nv.value.clone(),
                     _ => {
@@ -122,23 +435,17 @@ fn get_fields(input: &syn::DeriveInput) -> syn::Result<(Vec<Field>, Extras)> {
                 };
 
                 let default_object = DefaultField {
-                    field: Field { ident, ty },
+                    field: Field {
+                        ident,
+                        ty,
+                        attrs,
+                        parser,
+                        rules,
+                    },
                     default,
                 };
 
-                match &mut extras {
-                    Extras::None => extras = Extras::Default(vec![default_object]),
-                    Extras::Default(list) => list.push(default_object),
-                    Extras::Flags(_) => {
-                        return Err(syn::Error::new_spanned(
-                            meta,
-                            "both defaults and flags on a strand are not supported",
-                        ))
-                    }
-                    Extras::Trail(_) => {
-                        panic!("both defaults and trails on a strand are not supported")
-                    }
-                }
+                fields.defaults.push(default_object);
             } else if let Some(meta) = meta_map.get("flag") {
                 let flag_name: String = match meta {
                     syn::Meta::NameValue(n) => {
@@ -153,143 +460,562 @@ fn get_fields(input: &syn::DeriveInput) -> syn::Result<(Vec<Field>, Extras)> {
                     }
                 };
 
-                let flag_type = match ty {
-                    syn::Type::Path(syn::TypePath { qself: None, path }) => {
-                        let seg = path.segments.first().unwrap();
-                        if seg.ident == "Option" {
-                            match &seg.arguments {
-                                syn::PathArguments::AngleBracketed(
-                                    syn::AngleBracketedGenericArguments { args, .. },
-                                ) => {
-                                    if args.len() != 1 {
-                                        return Err(syn::Error::new_spanned(
-                                            ty,
-                                            "expected single type argument on Option, \"Option<T>\"",
-                                        ));
-                                    }
-                                    match args.first().unwrap() {
-                                        syn::GenericArgument::Type(ty) => ty,
-                                        _ => return Err(syn::Error::new_spanned(
-                                            ty,
-                                            "expected single type argument on Option, \"Option<T>\"",
-                                        )),
-                                    }
-                                }
-                                _ => {
-                                    return Err(syn::Error::new_spanned(
-                                        ty,
-                                        "expected single type argument on Option, \"Option<T>\"",
-                                    ))
-                                }
-                            }
-                        } else {
-                            return Err(syn::Error::new_spanned(
-                                ty,
-                                "expected single type argument on Option, \"Option<T>\"",
-                            ));
-                        }
+                let fallback = match (meta_map.get("required"), meta_map.get("default")) {
+                    (Some(_), Some(meta)) => {
+                        return Err(syn::Error::new_spanned(
+                            meta,
+                            "a required flag can't have a default",
+                        ))
                     }
-                    _ => {
+                    (Some(syn::Meta::Path(_)), None) => Fallback::Required,
+                    (Some(meta), None) => {
+                        return Err(syn::Error::new_spanned(meta, "expected, \"required\""))
+                    }
+                    (None, Some(syn::Meta::NameValue(nv))) => Fallback::Default(nv.value.clone()),
+                    (None, Some(meta)) => {
                         return Err(syn::Error::new_spanned(
-                            ty,
-                            "expected single type argument on Option, \"Option<T>\"",
+                            meta,
+                            "expected default value \"default = <expr>\"",
                         ))
                     }
+                    (None, None) => Fallback::None,
                 };
 
-                let is_unit = match flag_type {
-                    Type::Path(syn::TypePath { qself: None, path }) => {
-                        path.segments.last().map_or(false, |path_segment| {
-                            path_segment.ident == "Trigger" && path_segment.arguments.is_empty()
-                        })
+                let flag_type = if !matches!(fallback, Fallback::None) {
+                    // The field holds the value itself rather than an Option
+                    if meta_map.contains_key("count") {
+                        return Err(syn::Error::new_spanned(
+                            meta_map.get("count"),
+                            "a counted flag is always set, so can't be required or have a default",
+                        ));
+                    }
+
+                    if inner_type(ty, "Vec").is_some() {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "a repeated flag can't be required or have a default",
+                        ));
+                    }
+
+                    if inner_type(ty, "Option").is_some() {
+                        return Err(syn::Error::new_spanned(
+                            meta_map.get("required").or(meta_map.get("default")),
+                            "a required flag or one with a default holds its value, expected \"T\" rather than \"Option<T>\"",
+                        ));
                     }
-                    _ => false,
-                };
 
-                let flag_type = if !is_unit {
-                    FlagType::Value(flag_type)
+                    FlagType::Value(ty)
+                } else if meta_map.contains_key("count") {
+                    match meta_map.get("count") {
+                        Some(syn::Meta::Path(_)) => FlagType::Count(ty),
+                        meta => return Err(syn::Error::new_spanned(meta, "expected, \"count\"")),
+                    }
+                } else if let Some(item) = inner_type(ty, "Vec") {
+                    FlagType::Repeated(item)
+                } else if let Some(value) = inner_type(ty, "Option") {
+                    let is_unit = match value {
+                        Type::Path(syn::TypePath { qself: None, path }) => {
+                            path.segments.last().is_some_and(|path_segment| {
+                                path_segment.ident == "Trigger" && path_segment.arguments.is_empty()
+                            })
+                        }
+                        _ => false,
+                    };
+
+                    if !is_unit {
+                        FlagType::Value(value)
+                    } else {
+                        FlagType::Trigger
+                    }
                 } else {
-                    FlagType::Trigger
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "expected \"Option<T>\", or \"Vec<T>\" for a repeated flag",
+                    ));
+                };
+
+                let once = match meta_map.get("once") {
+                    Some(syn::Meta::Path(_)) => true,
+                    Some(meta) => return Err(syn::Error::new_spanned(meta, "expected, \"once\"")),
+                    None => false,
+                };
+
+                if let (FlagType::Repeated(_) | FlagType::Count(_), true) = (flag_type, once) {
+                    return Err(syn::Error::new_spanned(
+                        meta_map.get("once"),
+                        "\"once\" is only supported on flags that are set a single time",
+                    ));
+                }
+
+                if let (FlagType::Trigger | FlagType::Count(_), false) =
+                    (flag_type, rules.is_empty())
+                {
+                    return Err(syn::Error::new_spanned(
+                        strand_meta,
+                        "rules are only supported on flags that take a value",
+                    ));
+                }
+
+                if let (FlagType::Trigger | FlagType::Count(_), Parser::With(_) | Parser::Arg) =
+                    (flag_type, &parser)
+                {
+                    return Err(syn::Error::new_spanned(
+                        strand_meta,
+                        "parsers are only supported on flags that take a value",
+                    ));
+                }
+
+                let short: Option<char> = match meta_map.get("short") {
+                    Some(meta) => {
+                        let lit: syn::LitChar = match meta {
+                            syn::Meta::NameValue(n) => {
+                                syn::parse(n.value.to_token_stream().into())?
+                            }
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    meta,
+                                    "expected character, \"short = <char>\"",
+                                ))
+                            }
+                        };
+
+                        let c = lit.value();
+
+                        if c == '-' || c == '=' || c.is_ascii_digit() || c.is_whitespace() {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "expected a character that isn't a digit, whitespace, '-' or '='",
+                            ));
+                        }
+
+                        Some(c)
+                    }
+                    None => None,
                 };
 
                 let flag_object = Flag {
                     ident,
                     name: flag_name,
+                    short,
                     flag_type,
+                    once,
+                    fallback,
+                    attrs,
+                    parser,
+                    rules,
                 };
 
-                match &mut extras {
-                    Extras::None => extras = Extras::Flags(vec![flag_object]),
-                    Extras::Flags(list) => list.push(flag_object),
-                    Extras::Default(_) => {
-                        return Err(syn::Error::new_spanned(
-                            meta,
-                            "both defaults and flags on a strand are not supported",
-                        ))
-                    }
-                    Extras::Trail(_) => {
-                        panic!("both flags and trails on a strand are not supported")
-                    }
+                if fields
+                    .flags
+                    .iter()
+                    .any(|flag| flag.name == flag_object.name)
+                {
+                    return Err(syn::Error::new_spanned(meta, "flag already exists"));
+                }
+
+                if short.is_some() && fields.flags.iter().any(|flag| flag.short == short) {
+                    return Err(syn::Error::new_spanned(
+                        meta_map.get("short"),
+                        "short flag already exists",
+                    ));
                 }
+
+                fields.flags.push(flag_object);
             } else if let Some(meta) = meta_map.get("trail") {
                 match meta {
                     syn::Meta::Path(_) => (),
                     _ => return Err(syn::Error::new_spanned(meta, "expected, \"trail\"")),
                 };
 
-                match &mut extras {
-                    Extras::None => extras = Extras::Trail(Field { ident, ty }),
-                    Extras::Flags(_) => {
-                        return Err(syn::Error::new_spanned(
+                if fields.trail.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "a trail can only appear once on a strand",
+                    ));
+                }
+
+                if fields.many.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "a trail can't be used with \"many\", which takes the rest of the input",
+                    ));
+                }
+
+                fields.trail = Some(Field {
+                    ident,
+                    ty,
+                    attrs,
+                    parser,
+                    rules,
+                });
+            } else if let Some(meta) = meta_map.get("many") {
+                match meta {
+                    syn::Meta::Path(_) => (),
+                    _ => return Err(syn::Error::new_spanned(meta, "expected, \"many\"")),
+                };
+
+                if fields.many.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "\"many\" can only appear once on a strand",
+                    ));
+                }
+
+                if fields.trail.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "\"many\" can't be used with a trail, as it takes the rest of the input",
+                    ));
+                }
+
+                let item = inner_type(ty, "Vec").ok_or(syn::Error::new_spanned(
+                    ty,
+                    "expected single type argument on Vec, \"Vec<T>\"",
+                ))?;
+
+                let count = |name: &str| -> syn::Result<Option<usize>> {
+                    match meta_map.get(name) {
+                        Some(syn::Meta::NameValue(nv)) => {
+                            let lit: syn::LitInt = syn::parse(nv.value.to_token_stream().into())?;
+                            Ok(Some(lit.base10_parse()?))
+                        }
+                        Some(meta) => Err(syn::Error::new_spanned(
                             meta,
-                            "both flags and trails on a strand are not supported",
-                        ))
+                            format!("expected count, \"{} = <usize>\"", name),
+                        )),
+                        None => Ok(None),
                     }
-                    Extras::Default(_) => {
-                        return Err(syn::Error::new_spanned(
-                            meta,
-                            "both defaults and trails on a strand are not supported",
-                        ))
-                    }
-                    Extras::Trail(_) => panic!("a trail can only appear once on a strand"),
+                };
+
+                let min = count("min")?.unwrap_or(0);
+                let max = count("max")?;
+
+                if max.is_some_and(|max| max < min || max == 0) {
+                    return Err(syn::Error::new_spanned(
+                        meta_map.get("max"),
+                        "\"max\" must be at least 1 and no less than \"min\"",
+                    ));
                 }
-            } else {
-                return Err(syn::Error::new_spanned(
-                    strand_meta,
-                    "expected attribute, \"#[strand(optional / flag / flag = <type>)]\"",
-                ));
+
+                fields.many = Some(Many {
+                    field: Field {
+                        ident,
+                        ty,
+                        attrs,
+                        parser,
+                        rules,
+                    },
+                    item,
+                    min,
+                    max,
+                });
             }
         } else {
-            fields.push(Field { ident, ty });
+            let positional = Field {
+                ident,
+                ty,
+                attrs,
+                parser: Parser::default(),
+                rules: Rules::default(),
+            };
+
+            fields.positional.push(positional);
         }
     }
 
-    Ok((fields, extras))
+    Ok(fields)
 }
 
-fn construct_internal(fields: Vec<Field>, extras: Extras) -> TokenStream {
-    let field_constructors = construct_fields(&fields);
-    let other = match &extras {
-        Extras::None => quote::quote!(),
-        Extras::Default(t0) => construct_defaults(t0),
-        Extras::Flags(t0) => construct_flags(t0),
-        Extras::Trail(t0) => construct_trail(t0),
+/// The type argument of `ty` if it is `wrapper<T>`, such as `Vec<T>`
+fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return None,
+    };
+
+    let segment = path.segments.last()?;
+
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    let args = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(v) if v.args.len() == 1 => &v.args,
+        _ => return None,
+    };
+
+    match args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+fn get_parser(meta_map: &HashMap<&str, syn::Meta>) -> syn::Result<Parser> {
+    match (meta_map.get("parse_with"), meta_map.get("arg")) {
+        (Some(meta), Some(_)) => Err(syn::Error::new_spanned(
+            meta,
+            "a field can only have one of \"parse_with\" and \"arg\"",
+        )),
+        (Some(syn::Meta::NameValue(nv)), None) => Ok(Parser::With(nv.value.clone())),
+        (Some(meta), None) => Err(syn::Error::new_spanned(
+            meta,
+            "expected function, \"parse_with = <fn>\"",
+        )),
+        (None, Some(syn::Meta::Path(_))) => Ok(Parser::Arg),
+        (None, Some(meta)) => Err(syn::Error::new_spanned(meta, "expected, \"arg\"")),
+        (None, None) => Ok(Parser::FromStr),
+    }
+}
+
+fn get_rules(meta_map: &HashMap<&str, syn::Meta>) -> syn::Result<Rules> {
+    let value = |name: &str, expected: &str| -> syn::Result<Option<syn::Expr>> {
+        match meta_map.get(name) {
+            Some(syn::Meta::NameValue(nv)) => Ok(Some(nv.value.clone())),
+            Some(meta) => Err(syn::Error::new_spanned(meta, expected)),
+            None => Ok(None),
+        }
+    };
+
+    let range = value("range", "expected range, \"range = <range>\"")?;
+
+    let choices = match value("choices", "expected list, \"choices = [..]\"")? {
+        Some(expr) => {
+            let parser = |input: syn::parse::ParseStream| {
+                let content;
+                syn::bracketed!(content in input);
+                syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated(&content)
+            };
+
+            let choices = syn::parse::Parser::parse2(parser, expr.to_token_stream())?;
+
+            if choices.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    expr,
+                    "expected non-empty list, \"choices = [..]\"",
+                ));
+            }
+
+            Some(choices.into_iter().collect())
+        }
+        None => None,
+    };
+
+    let min_len = match value("min_len", "expected length, \"min_len = <usize>\"")? {
+        Some(expr) => {
+            let lit: syn::LitInt = syn::parse(expr.to_token_stream().into())?;
+            Some(lit.base10_parse()?)
+        }
+        None => None,
+    };
+
+    let validate = match value("validate", "expected function, \"validate = <path>\"")? {
+        Some(syn::Expr::Path(path)) => Some(path),
+        Some(expr) => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "expected function, \"validate = <path>\"",
+            ))
+        }
+        None => None,
+    };
+
+    Ok(Rules {
+        range,
+        choices,
+        min_len,
+        validate,
+    })
+}
+
+/// Parses the input into the fields, binding each of them to a local named after it
+pub(crate) fn construct_internal(fields: &Fields) -> TokenStream {
+    let slots: Vec<Field> = fields
+        .positional
+        .iter()
+        .cloned()
+        .chain(
+            fields
+                .defaults
+                .iter()
+                .map(|default_field| default_field.field.clone()),
+        )
+        .collect();
+
+    let slot_setters = slots.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = field.ty;
+
+        quote!(let mut #ident: Option<#ty> = None;)
+    });
+
+    let many_setter = fields.many.as_ref().map(|many| {
+        let ident = &many.field.ident;
+        let ty = many.field.ty;
+
+        quote!(let mut #ident: #ty = ::std::vec::Vec::new();)
+    });
+
+    let (flag_setters, flag_matchers) = construct_flags(&fields.flags);
+
+    // `--` only ends flag parsing, so without flags it is an argument like any other
+    let (separator_setter, separator_matcher) = if !fields.flags.is_empty() {
+        (
+            quote!(let mut __roped_flags_ended = false;),
+            quote! {
+                if !__roped_flags_ended && __roped_arg == "--" {
+                    __roped_flags_ended = true;
+                    __roped_index += 1;
+                    continue;
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    let (rest_setter, overflow) = match fields.trail {
+        Some(_) => (
+            quote!(let __roped_arg_rest = __roped_input;),
+            quote! {
+                {
+                    __roped_input = __roped_arg_rest;
+                    break;
+                }
+            },
+        ),
+        None => (
+            quote!(),
+            quote! {
+                return Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(
+                        __roped_index,
+                        ::roped::error::ErrorType::Unexpected(__roped_arg.into_owned()),
+                    )
+                    .with_span(__roped_arg_span),
+                )))
+            },
+        ),
+    };
+
+    // Once the positional fields are filled the arguments are collected by many, if there is one
+    let overflow = match &fields.many {
+        Some(many) => construct_many(many, overflow),
+        None => overflow,
+    };
+
+    let slot_matcher = if slots.is_empty() {
+        match fields.many {
+            Some(_) => quote! {
+                #overflow
+                __roped_index += 1;
+            },
+            None => overflow,
+        }
+    } else {
+        let positions = slots.iter().enumerate().map(|(i, field)| {
+            let ident = &field.ident;
+            let with_field = construct_with_field(ident, field.ty);
+            let reason = construct_reason();
+            let rules = construct_rules(
+                &field.rules,
+                ident,
+                field.ty,
+                quote!(__roped_arg),
+                quote!(__roped_arg_span),
+            );
+
+            let parse = construct_parse(&field.parser, field.ty, quote!(&__roped_arg));
+
+            quote! {
+                #i => match #parse {
+                    Ok(__roped_v) => {
+                        #rules
+                        #ident = Some(__roped_v);
+                    }
+                    Err(__roped_err) => return Err(::roped::error::Error::Internal(Box::new(
+                        ::roped::error::InternalError::new(
+                            __roped_index,
+                            ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
+                                arg: __roped_arg.into_owned(),
+                                parse_type: ::roped::error::ArgType::Arg,
+                                reason: #reason,
+                            }),
+                        )
+                        .with_span(__roped_arg_span)
+                        #with_field,
+                    ))),
+                },
+            }
+        });
+
+        quote! {
+            match __roped_position {
+                #(#positions)*
+                _ => #overflow,
+            }
+
+            __roped_position += 1;
+            __roped_index += 1;
+        }
+    };
+
+    let position_setter = if slots.is_empty() {
+        quote!()
+    } else {
+        quote!(let mut __roped_position = 0usize;)
+    };
+
+    let field_constructors = construct_fields(&fields.positional);
+    let default_constructors = construct_defaults(&fields.defaults);
+    let flag_constructors = construct_flag_fallbacks(&fields.flags);
+    let many_constructor = match &fields.many {
+        Some(many) => construct_many_min(many),
+        None => quote!(),
+    };
+    let trail = match &fields.trail {
+        Some(field) => construct_trail(field),
+        None => quote!(),
+    };
+
+    // The span of the argument goes unused if every argument can only begin the trail
+    let span_binding = if slots.is_empty()
+        && fields.flags.is_empty()
+        && fields.many.is_none()
+        && fields.trail.is_some()
+    {
+        quote!(_)
+    } else {
+        quote!(__roped_arg_span)
     };
-    let constructor = construct_constructor(&fields, extras);
 
     quote::quote! {
-        #field_constructors
-        #other
-        let this = Self {
-            #constructor
-        };
+        #(#slot_setters)*
+        #many_setter
+        #flag_setters
+        #separator_setter
+        #position_setter
+
+        loop {
+            #rest_setter
+
+            let (__roped_arg, #span_binding) = match __roped_input.next_arg_spanned() {
+                Ok(Some(__roped_v)) => __roped_v,
+                Ok(None) => break,
+                Err(__roped_variant) => return Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(__roped_index, __roped_variant).with_span(__roped_input.rest_span()),
+                ))),
+            };
 
-        if let Some(overflow) = input {
-            return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                index,
-                variant: ::roped::error::ErrorType::Unexpected(overflow.parse_once().arg.get_internal().to_string()),
-            }))
+            #separator_matcher
+            #flag_matchers
+            #slot_matcher
         }
+
+        #field_constructors
+        #default_constructors
+        #flag_constructors
+        #many_constructor
+        #trail
     }
 }
 
@@ -297,75 +1023,119 @@ fn construct_fields(fields: &[Field]) -> TokenStream {
     let mut field_constructors: Vec<TokenStream> = Vec::with_capacity(fields.len());
 
     for field in fields {
-        let ident = field.ident;
+        let ident = &field.ident;
         let ty = field.ty;
+        let with_field = construct_with_field(ident, ty);
 
         let quote = quote::quote! {
-            let s = match input {
-                Some(v) => v,
-                None => return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                    index,
-                    variant: ::roped::error::ErrorType::Expected(::roped::error::ArgType::Arg)
-                }))
+            let #ident: #ty = match #ident {
+                Some(__roped_v) => __roped_v,
+                None => return Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(
+                        __roped_index,
+                        ::roped::error::ErrorType::Expected(::roped::error::ArgType::Arg),
+                    )
+                    .with_span(__roped_input.rest_span())
+                    #with_field,
+                ))),
             };
+        };
 
-            let pair = s.parse_once();
+        field_constructors.push(quote);
+    }
 
-            let #ident: #ty = match std::str::FromStr::from_str(pair.arg.get_internal()) {
-                Ok(v) => v,
-                Err(_) => return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                    index,
-                    variant: ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
-                        arg: pair.arg.get_internal().to_string(),
-                        parse_type: ::roped::error::ArgType::Arg,
-                    })
-                })),
-            };
+    quote!(#(#field_constructors)*)
+}
 
-            index += 1;
+/// Parses an argument into many, `overflow` being run instead if it is full
+fn construct_many(many: &Many, overflow: TokenStream) -> TokenStream {
+    let ident = &many.field.ident;
+    let item = many.item;
+
+    let with_field = construct_with_field(ident, item);
+    let reason = construct_reason();
+    let parse = construct_parse(&many.field.parser, item, quote!(&__roped_arg));
+    let rules = construct_rules(
+        &many.field.rules,
+        ident,
+        item,
+        quote!(__roped_arg),
+        quote!(__roped_arg_span),
+    );
+
+    let max_check = match many.max {
+        Some(max) => quote! {
+            if #ident.len() == #max {
+                #overflow
+            }
+        },
+        None => quote!(),
+    };
 
-            input = pair.trail;
-        };
+    quote! {
+        {
+            #max_check
 
-        field_constructors.push(quote);
+            match #parse {
+                Ok(__roped_v) => {
+                    #rules
+                    #ident.push(__roped_v);
+                }
+                Err(__roped_err) => return Err(::roped::error::Error::Internal(Box::new(
+                    ::roped::error::InternalError::new(
+                        __roped_index,
+                        ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
+                            arg: __roped_arg.into_owned(),
+                            parse_type: ::roped::error::ArgType::Arg,
+                            reason: #reason,
+                        }),
+                    )
+                    .with_span(__roped_arg_span)
+                    #with_field,
+                ))),
+            }
+        }
     }
+}
 
-    quote!(#(#field_constructors)*)
+/// Checks that many was given at least as many arguments as its minimum
+fn construct_many_min(many: &Many) -> TokenStream {
+    if many.min == 0 {
+        return quote!();
+    }
+
+    let ident = &many.field.ident;
+    let min = many.min;
+    let with_field = construct_with_field(ident, many.item);
+
+    quote! {
+        if #ident.len() < #min {
+            return Err(::roped::error::Error::Internal(Box::new(
+                ::roped::error::InternalError::new(
+                    __roped_index,
+                    ::roped::error::ErrorType::Expected(::roped::error::ArgType::Arg),
+                )
+                .with_span(__roped_input.rest_span())
+                #with_field,
+            )));
+        }
+    }
 }
 
 fn construct_defaults(defaults: &[DefaultField]) -> TokenStream {
     let mut field_constructors: Vec<TokenStream> = Vec::with_capacity(defaults.len());
 
     for default_field in defaults {
-        let ident = default_field.field.ident;
+        let ident = &default_field.field.ident;
         let ty = default_field.field.ty;
 
         let expr = &default_field.default;
 
         let quote = quote::quote! {
-            let #ident: #ty = match input {
-                Some(v) => {
-                    let pair = v.parse_once();
-
-                    let out: #ty = match std::str::FromStr::from_str(pair.arg.get_internal()) {
-                        Ok(v) => v,
-                        Err(_) => return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                            index,
-                            variant: ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
-                                arg: pair.arg.get_internal().to_string(),
-                                parse_type: ::roped::error::ArgType::Arg,
-                            })
-                        })),
-                    };
-
-                    input = pair.trail;
-
-                    out
-                },
+            let #ident: #ty = match #ident {
+                Some(__roped_v) => __roped_v,
                 None => #expr,
             };
-
-            index += 1;
         };
 
         field_constructors.push(quote);
@@ -374,149 +1144,673 @@ fn construct_defaults(defaults: &[DefaultField]) -> TokenStream {
     quote!(#(#field_constructors)*)
 }
 
-fn construct_flags(flags: &[Flag]) -> TokenStream {
+/// Constructs the setters for the flags, and the matchers that parse an argument as a flag,
+/// moving on to the next argument if it is one
+fn construct_flags(flags: &[Flag]) -> (TokenStream, TokenStream) {
+    if flags.is_empty() {
+        return (quote!(), quote!());
+    }
+
     let mut flag_setters: Vec<TokenStream> = Vec::with_capacity(flags.len());
-    let mut flag_matchers: Vec<TokenStream> = Vec::with_capacity(flags.len());
+    let mut long_matchers: Vec<TokenStream> = Vec::with_capacity(flags.len());
+    let mut short_matchers: Vec<TokenStream> = Vec::with_capacity(flags.len());
 
     for flag in flags {
-        let ident = flag.ident;
+        let ident = &flag.ident;
         let name = &flag.name;
+        let long = format!("--{}", name);
 
-        let (set_quote, match_quote) = match flag.flag_type {
+        let (set_quote, long_quote) = match flag.flag_type {
             FlagType::Trigger => (
-                quote!(let mut #ident: Option<Trigger> = None;),
-                quote! {
-                    #name => #ident = Some(Trigger),
+                quote!(let mut #ident: Option<::roped::base_types::Trigger> = None;),
+                {
+                    let unexpected_value = flag_error(
+                        quote!(#long),
+                        quote!(::roped::error::FlagErrType::UnexpectedValue),
+                        quote!(.with_span(__roped_arg_span)),
+                    );
+
+                    let once = construct_once(flag, quote!(#long));
+
+                    quote! {
+                        #name => match __roped_flag_value {
+                            None => {
+                                #once
+                                #ident = Some(::roped::base_types::Trigger);
+                            }
+                            Some(_) => #unexpected_value,
+                        },
+                    }
                 },
             ),
-            FlagType::Value(ty) => (
-                quote!(let mut #ident: Option<#ty> = None;),
+            FlagType::Count(ty) => (quote!(let mut #ident: #ty = 0;), {
+                let unexpected_value = flag_error(
+                    quote!(#long),
+                    quote!(::roped::error::FlagErrType::UnexpectedValue),
+                    quote!(.with_span(__roped_arg_span)),
+                );
+
                 quote! {
-                    #name => {
-                        if let Some(s) = input {
-                            let pair = s.parse_once();
-
-                            let out: #ty = match std::str::FromStr::from_str(pair.arg.get_internal()) {
-                                Ok(v) => v,
-                                Err(_) => return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                                    index,
-                                    variant: ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
-                                        arg: pair.arg.get_internal().to_string(),
-                                        parse_type: ::roped::error::ArgType::Arg,
-                                    })
-                                })),
-                            };
-
-                            input = pair.trail;
-
-                            #ident = Some(out);
-                        } else {
-                            return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                                index,
-                                variant: ::roped::error::ErrorType::Expected(::roped::error::ArgType::Flag),
-                            }))
-                        }
+                    #name => match __roped_flag_value {
+                        None => #ident = #ident.saturating_add(1),
+                        Some(_) => #unexpected_value,
                     },
+                }
+            }),
+            FlagType::Value(ty) => (quote!(let mut #ident: Option<#ty> = None;), {
+                let setter = construct_flag_value(flag, ty, quote!(#long));
+
+                quote! {
+                    #name => #setter,
+                }
+            }),
+            FlagType::Repeated(item) => (
+                quote!(let mut #ident: ::std::vec::Vec<#item> = ::std::vec::Vec::new();),
+                {
+                    let setter = construct_flag_value(flag, item, quote!(#long));
+
+                    quote! {
+                        #name => #setter,
+                    }
                 },
             ),
         };
 
         flag_setters.push(set_quote);
-        flag_matchers.push(match_quote);
+        long_matchers.push(long_quote);
+
+        let short = match flag.short {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let short_name = format!("-{}", short);
+
+        let short_quote = match flag.flag_type {
+            FlagType::Trigger => {
+                let unexpected_value = flag_error(
+                    quote!(#short_name),
+                    quote!(::roped::error::FlagErrType::UnexpectedValue),
+                    quote!(.with_span(__roped_arg_span)),
+                );
+
+                let once = construct_once(flag, quote!(#short_name));
+
+                quote! {
+                    #short => {
+                        if __roped_flag_last && __roped_flag_value.is_some() {
+                            #unexpected_value
+                        }
+
+                        #once
+                        #ident = Some(::roped::base_types::Trigger);
+                    }
+                }
+            }
+            FlagType::Count(_) => {
+                let unexpected_value = flag_error(
+                    quote!(#short_name),
+                    quote!(::roped::error::FlagErrType::UnexpectedValue),
+                    quote!(.with_span(__roped_arg_span)),
+                );
+
+                quote! {
+                    #short => {
+                        if __roped_flag_last && __roped_flag_value.is_some() {
+                            #unexpected_value
+                        }
+
+                        #ident = #ident.saturating_add(1);
+                    }
+                }
+            }
+            FlagType::Value(ty) | FlagType::Repeated(ty) => {
+                let not_last = flag_error(
+                    quote!(#short_name),
+                    quote!(::roped::error::FlagErrType::NotLast),
+                    quote!(.with_span(__roped_arg_span)),
+                );
+                let setter = construct_flag_value(flag, ty, quote!(#short_name));
+
+                quote! {
+                    #short => {
+                        if !__roped_flag_last {
+                            #not_last
+                        }
+
+                        #setter
+                    }
+                }
+            }
+        };
+
+        short_matchers.push(short_quote);
     }
 
-    quote! {
-        #(#flag_setters)*
+    // Only parses short flags if there are any, otherwise such arguments are positional
+    let short_branch = if short_matchers.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            else if let Some(__roped_flag_group) = __roped_arg
+                .strip_prefix('-')
+                .filter(|__roped_group| !__roped_group.is_empty() && !__roped_group.starts_with(|__roped_c: char| __roped_c.is_ascii_digit()))
+            {
+                let (__roped_flag_shorts, __roped_flag_value) = match __roped_flag_group.split_once('=') {
+                    Some((__roped_shorts, __roped_value)) => (__roped_shorts, Some(__roped_value)),
+                    None => (__roped_flag_group, None),
+                };
 
-        while let Some(s) = input {
-            let pair = s.parse_once();
+                let __roped_flag_form = if __roped_flag_value.is_some() {
+                    ::roped::error::FlagForm::ShortAssigned
+                } else if __roped_flag_shorts.chars().count() > 1 {
+                    ::roped::error::FlagForm::Combined
+                } else {
+                    ::roped::error::FlagForm::Short
+                };
 
-            input = pair.trail;
+                let mut __roped_flag_chars = __roped_flag_shorts.chars().peekable();
 
-            if let Some(identifier) = ::roped::parsr::parser::trim::Trim::trim_once(pair.arg.get_internal(), ::roped::Matcher::Single(MatcherSingle::Ident("--"))) {
-                match identifier {
-                    #(#flag_matchers)*
-                    _ => return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                        index,
-                        variant: ::roped::error::ErrorType::InvalidFlag(pair.arg.get_internal().to_string()),
-                    })),
+                if __roped_flag_chars.peek().is_none() {
+                    return Err(::roped::error::Error::Internal(Box::new(
+                        ::roped::error::InternalError::new(
+                            __roped_index,
+                            ::roped::error::ErrorType::InvalidFlag(__roped_arg.into_owned()),
+                        )
+                        .with_span(__roped_arg_span),
+                    )))
+                }
+
+                while let Some(__roped_flag_char) = __roped_flag_chars.next() {
+                    let __roped_flag_last = __roped_flag_chars.peek().is_none();
+
+                    match __roped_flag_char {
+                        #(#short_matchers)*
+                        _ => return Err(::roped::error::Error::Internal(Box::new(
+                            ::roped::error::InternalError::new(
+                                __roped_index,
+                                ::roped::error::ErrorType::InvalidFlag(format!("-{}", __roped_flag_char)),
+                            )
+                            .with_span(__roped_arg_span),
+                        ))),
+                    }
                 }
-            } else {
-                return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                    index,
-                    variant: ::roped::error::ErrorType::Unexpected(pair.arg.get_internal().to_string()),
-                }))
+
+                __roped_index += 1;
+                continue;
             }
+        }
+    };
+
+    let flag_setters = quote!(#(#flag_setters)*);
+
+    // The long flags are suggested when a long flag doesn't exist
+    let candidates = flags.iter().map(|flag| format!("--{}", flag.name));
+
+    let flag_matchers = quote! {
+        if !__roped_flags_ended {
+            if let Some(__roped_identifier) = __roped_arg.strip_prefix("--") {
+                let (__roped_flag_name, __roped_flag_value) = match __roped_identifier.split_once('=') {
+                    Some((__roped_name, __roped_value)) => (__roped_name, Some(__roped_value)),
+                    None => (__roped_identifier, None),
+                };
 
-            index += 1;
+                let __roped_flag_form = if __roped_flag_value.is_some() {
+                    ::roped::error::FlagForm::LongAssigned
+                } else {
+                    ::roped::error::FlagForm::Long
+                };
+
+                match __roped_flag_name {
+                    #(#long_matchers)*
+                    _ => {
+                        let __roped_flag = format!("--{}", __roped_flag_name);
+                        let __roped_suggestions = ::roped::error::suggestions(&__roped_flag, &[#(#candidates),*]);
+
+                        return Err(::roped::error::Error::Internal(Box::new(
+                            ::roped::error::InternalError::new(
+                                __roped_index,
+                                ::roped::error::ErrorType::InvalidFlag(__roped_flag),
+                            )
+                            .with_span(__roped_arg_span)
+                            .with_suggestions(__roped_suggestions),
+                        )))
+                    }
+                }
+
+                __roped_index += 1;
+                continue;
+            } #short_branch
+        }
+    };
+
+    (flag_setters, flag_matchers)
+}
+
+/// Sets a flag that takes a value, from either `__roped_flag_value` or the next argument, `ty`
+/// being the type of a single value
+fn construct_flag_value(flag_object: &Flag, ty: &Type, flag: TokenStream) -> TokenStream {
+    let ident = &flag_object.ident;
+    let once = construct_once(flag_object, flag.clone());
+    let store = match flag_object.flag_type {
+        FlagType::Repeated(_) => quote!(#ident.push(__roped_v);),
+        _ => quote!(#ident = Some(__roped_v);),
+    };
+    let with_field = construct_with_field(ident, ty);
+    let reason = construct_reason();
+    let parse = construct_parse(&flag_object.parser, ty, quote!(&__roped_flag_value));
+    let rules = construct_rules(
+        &flag_object.rules,
+        ident,
+        ty,
+        quote!(__roped_flag_value),
+        quote!(__roped_flag_span),
+    );
+
+    let expected_value = flag_error(
+        flag.clone(),
+        quote!(::roped::error::FlagErrType::ExpectedValue),
+        quote!(.with_span(__roped_arg_span) #with_field),
+    );
+    let parse_error = flag_error(
+        flag,
+        quote! {
+            ::roped::error::FlagErrType::Parse(::roped::error::ParseErr {
+                arg: __roped_flag_value.into_owned(),
+                parse_type: ::roped::error::ArgType::Flag,
+                reason: #reason,
+            })
+        },
+        quote!(.with_span(__roped_flag_span) #with_field),
+    );
+
+    quote! {
+        {
+            #once
+
+            let (__roped_flag_value, __roped_flag_span) = match __roped_flag_value {
+                Some(__roped_v) => (::std::borrow::Cow::Borrowed(__roped_v), __roped_arg_span.clone()),
+                None => match __roped_input.next_arg_spanned() {
+                    Ok(Some(__roped_v)) => {
+                        __roped_index += 1;
+                        __roped_v
+                    }
+                    Ok(None) => #expected_value,
+                    Err(__roped_variant) => return Err(::roped::error::Error::Internal(Box::new(
+                        ::roped::error::InternalError::new(__roped_index, __roped_variant).with_span(__roped_input.rest_span()),
+                    ))),
+                },
+            };
+
+            match #parse {
+                Ok(__roped_v) => {
+                    #rules
+                    #store
+                }
+                Err(__roped_err) => #parse_error,
+            }
         }
     }
 }
 
-fn construct_trail(field: &Field) -> TokenStream {
-    let ident = field.ident;
-    let ty = field.ty;
+/// Unwraps the flags that are required or have a default, returning an error if a required flag
+/// wasn't given
+fn construct_flag_fallbacks(flags: &[Flag]) -> TokenStream {
+    let mut flag_constructors: Vec<TokenStream> = Vec::with_capacity(flags.len());
 
-    quote::quote! {
-        let s = match input.take() {
-            Some(v) => v.get_internal().to_string(),
-            None => "".to_string(),
+    for flag in flags {
+        let ident = &flag.ident;
+        let long = format!("--{}", flag.name);
+
+        let ty = match flag.flag_type {
+            FlagType::Value(ty) => ty,
+            _ => continue,
         };
 
-        let #ident: #ty = match std::str::FromStr::from_str(&s) {
-            Ok(v) => v,
-            Err(_) => return Err(::roped::error::Error::Internal(::roped::error::InternalError {
-                index,
-                variant: ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
-                    arg: s,
-                    parse_type: ::roped::error::ArgType::Arg,
-                })
-            })),
+        let quote = match &flag.fallback {
+            Fallback::None => continue,
+            Fallback::Required => {
+                let with_field = construct_with_field(ident, ty);
+
+                quote! {
+                    let #ident: #ty = match #ident {
+                        Some(__roped_v) => __roped_v,
+                        None => return Err(::roped::error::Error::Internal(Box::new(
+                            ::roped::error::InternalError::new(
+                                __roped_index,
+                                ::roped::error::ErrorType::MissingFlag(#long.to_string()),
+                            )
+                            .with_span(__roped_input.rest_span())
+                            #with_field,
+                        ))),
+                    };
+                }
+            }
+            Fallback::Default(expr) => quote! {
+                let #ident: #ty = match #ident {
+                    Some(__roped_v) => __roped_v,
+                    None => #expr,
+                };
+            },
         };
+
+        flag_constructors.push(quote);
     }
+
+    quote!(#(#flag_constructors)*)
 }
 
-fn construct_constructor(fields: &[Field], extras: Extras) -> TokenStream {
-    let mut field_constructors: Vec<TokenStream> = Vec::with_capacity(fields.len());
+/// Returns an error if a flag marked `once` has already been set, and nothing otherwise
+fn construct_once(flag_object: &Flag, flag: TokenStream) -> TokenStream {
+    if !flag_object.once {
+        return quote!();
+    }
 
-    for field in fields {
-        let ident = field.ident;
+    let ident = &flag_object.ident;
+    let repeated = flag_error(
+        flag,
+        quote!(::roped::error::FlagErrType::Repeated),
+        quote!(.with_span(__roped_arg_span)),
+    );
 
-        let quote = quote::quote! {
-            #ident,
-        };
+    quote! {
+        if #ident.is_some() {
+            #repeated
+        }
+    }
+}
 
-        field_constructors.push(quote);
+/// Returns a `FlagErr` for the argument being parsed, `extra` being
+/// called on the `InternalError` to add its span and field
+fn flag_error(flag: TokenStream, variant: TokenStream, extra: TokenStream) -> TokenStream {
+    quote! {
+        return Err(::roped::error::Error::Internal(Box::new(
+            ::roped::error::InternalError::new(
+                __roped_index,
+                ::roped::error::ErrorType::Flag(::roped::error::FlagErr {
+                    flag: #flag.to_string(),
+                    arg: __roped_arg.to_string(),
+                    form: __roped_flag_form,
+                    variant: #variant,
+                }),
+            )
+            #extra,
+        )))
     }
+}
 
-    match extras {
-        Extras::None => (),
-        Extras::Default(t0) => {
-            for field in t0 {
-                let ident = field.field.ident;
+/// Casts `arg`, a `&str`, to the type of its field
+fn construct_parse(parser: &Parser, ty: &Type, arg: TokenStream) -> TokenStream {
+    match parser {
+        Parser::FromStr => quote!(<#ty as std::str::FromStr>::from_str(#arg)),
+        Parser::With(parse_with) => quote!((#parse_with)(#arg)),
+        Parser::Arg => {
+            quote!(<#ty as ::roped::arg::Arg<Self::State>>::parse_arg(#arg, __roped_state))
+        }
+    }
+}
 
-                field_constructors.push(quote! {
-                    #ident,
-                })
-            }
+/// Checks the cast value `__roped_v` against the rules of its field, `arg` being the value as it
+/// was entered and `span` its span
+fn construct_rules(
+    rules: &Rules,
+    ident: &syn::Ident,
+    ty: &Type,
+    arg: TokenStream,
+    span: TokenStream,
+) -> TokenStream {
+    let mut checks: Vec<TokenStream> = Vec::new();
+
+    let error = |rule: TokenStream| {
+        let name = ident.to_string();
+        let with_field = construct_with_field(ident, ty);
+
+        quote! {
+            return Err(::roped::error::Error::Internal(Box::new(
+                ::roped::error::InternalError::new(
+                    __roped_index,
+                    ::roped::error::ErrorType::Validation(::roped::error::ValidationErr {
+                        field: #name,
+                        arg: #arg.to_string(),
+                        rule: #rule,
+                    }),
+                )
+                .with_span(#span)
+                #with_field,
+            )))
         }
-        Extras::Flags(t0) => {
-            for flag in t0 {
-                let ident = flag.ident;
+    };
 
-                field_constructors.push(quote! {
-                    #ident,
-                })
+    if let Some(range) = &rules.range {
+        let text = source_text(range);
+        let error = error(quote!(::roped::error::Rule::Range(#text)));
+
+        checks.push(quote! {
+            if !(#range).contains(&__roped_v) {
+                #error
             }
+        });
+    }
+
+    if let Some(choices) = &rules.choices {
+        let choices = choices.iter();
+        let texts = choices.clone().map(source_text);
+        let error = error(quote!(::roped::error::Rule::Choices(&[#(#texts),*])));
+
+        checks.push(quote! {
+            if #(__roped_v != #choices)&&* {
+                #error
+            }
+        });
+    }
+
+    if let Some(min_len) = rules.min_len {
+        let error = error(quote!(::roped::error::Rule::MinLen(#min_len)));
+
+        checks.push(quote! {
+            if #arg.chars().count() < #min_len {
+                #error
+            }
+        });
+    }
+
+    if let Some(validate) = &rules.validate {
+        let error = error(quote!(::roped::error::Rule::Custom(
+            __roped_err.to_string()
+        )));
+
+        checks.push(quote! {
+            if let Err(__roped_err) = #validate(&__roped_v) {
+                #error
+            }
+        });
+    }
+
+    quote!(#(#checks)*)
+}
+
+/// Adds the name and type of a field to an `InternalError`
+fn construct_with_field(ident: &syn::Ident, ty: &Type) -> TokenStream {
+    let name = ident.to_string();
+    let ty = source_text(ty);
+
+    quote!(.with_field(#name, #ty))
+}
+
+/// The reason that `__roped_err` couldn't be parsed, if it can be displayed
+fn construct_reason() -> TokenStream {
+    quote! {
+        {
+            #[allow(unused_imports)]
+            use ::roped::error::{DisplayReason as _, NoReason as _};
+            (&::roped::error::Reason(&__roped_err)).reason()
         }
-        Extras::Trail(t0) => field_constructors.push({
-            let ident = t0.ident;
+    }
+}
 
-            quote! {
-                #ident,
+fn construct_trail(field: &Field) -> TokenStream {
+    let ident = &field.ident;
+    let ty = field.ty;
+
+    let with_field = construct_with_field(ident, ty);
+    let reason = construct_reason();
+    let parse = construct_parse(&field.parser, ty, quote!(__roped_trail));
+    let rules = construct_rules(
+        &field.rules,
+        ident,
+        ty,
+        quote!(__roped_trail),
+        quote!(__roped_trail_span),
+    );
+
+    quote::quote! {
+        let __roped_trail_span = __roped_input.rest_span();
+        let __roped_trail = __roped_input.take_rest();
+
+        let #ident: #ty = match #parse {
+            Ok(__roped_v) => {
+                #rules
+                __roped_v
             }
-        }),
+            Err(__roped_err) => return Err(::roped::error::Error::Internal(Box::new(
+                ::roped::error::InternalError::new(
+                    __roped_index,
+                    ::roped::error::ErrorType::Parse(::roped::error::ParseErr {
+                        arg: __roped_trail.to_string(),
+                        parse_type: ::roped::error::ArgType::Arg,
+                        reason: #reason,
+                    }),
+                )
+                .with_span(__roped_trail_span)
+                #with_field,
+            ))),
+        };
     }
+}
 
-    quote!(#(#field_constructors)*)
+pub(crate) fn construct_constructor(fields: &Fields, path: TokenStream) -> TokenStream {
+    let idents = &fields.members;
+
+    match fields.shape {
+        Shape::Named => quote!(#path { #(#idents,)* }),
+        Shape::Unnamed => quote!(#path(#(#idents,)*)),
+        Shape::Unit => quote!(#path),
+    }
+}
+
+pub(crate) fn construct_help(attrs: &[syn::Attribute], fields: &Fields) -> TokenStream {
+    let description = construct_description(attrs);
+
+    let mut args: Vec<TokenStream> = fields
+        .positional
+        .iter()
+        .map(|field| {
+            construct_arg(
+                &field.ident.to_string(),
+                quote!(::roped::help::ArgKind::Positional),
+                field.attrs,
+            )
+        })
+        .collect();
+
+    for default_field in &fields.defaults {
+        let default = source_text(&default_field.default);
+
+        args.push(construct_arg(
+            &default_field.field.ident.to_string(),
+            quote!(::roped::help::ArgKind::Default(#default)),
+            default_field.field.attrs,
+        ))
+    }
+
+    for flag in &fields.flags {
+        let value = match flag.flag_type {
+            FlagType::Trigger | FlagType::Count(_) => quote!(None),
+            FlagType::Value(_) | FlagType::Repeated(_) => {
+                let value = flag.ident.to_string();
+                quote!(Some(#value))
+            }
+        };
+        let short = match flag.short {
+            Some(c) => quote!(Some(#c)),
+            None => quote!(None),
+        };
+        let required = matches!(flag.fallback, Fallback::Required);
+        let default = match &flag.fallback {
+            Fallback::Default(expr) => {
+                let default = source_text(expr);
+                quote!(Some(#default))
+            }
+            _ => quote!(None),
+        };
+
+        args.push(construct_arg(
+            &flag.name,
+            quote!(::roped::help::ArgKind::Flag {
+                short: #short,
+                value: #value,
+                required: #required,
+                default: #default,
+            }),
+            flag.attrs,
+        ))
+    }
+
+    if let Some(many) = &fields.many {
+        let min = many.min;
+        let max = match many.max {
+            Some(max) => quote!(Some(#max)),
+            None => quote!(None),
+        };
+
+        args.push(construct_arg(
+            &many.field.ident.to_string(),
+            quote!(::roped::help::ArgKind::Many {
+                min: #min,
+                max: #max,
+            }),
+            many.field.attrs,
+        ))
+    }
+
+    if let Some(field) = &fields.trail {
+        args.push(construct_arg(
+            &field.ident.to_string(),
+            quote!(::roped::help::ArgKind::Trail),
+            field.attrs,
+        ))
+    }
+
+    quote! {
+        ::roped::help::Help::Command(::roped::help::Usage {
+            description: #description,
+            args: vec![#(#args),*],
+        })
+    }
+}
+
+fn construct_arg(name: &str, kind: TokenStream, attrs: &[syn::Attribute]) -> TokenStream {
+    let description = construct_description(attrs);
+
+    quote! {
+        ::roped::help::Arg {
+            name: #name,
+            kind: #kind,
+            description: #description,
+        }
+    }
+}
+
+/// The text of `tokens` as they were written, falling back to the tokens themselves
+fn source_text(tokens: &impl ToTokens) -> String {
+    tokens
+        .to_token_stream()
+        .into_iter()
+        .map(|token| {
+            token
+                .span()
+                .source_text()
+                .unwrap_or_else(|| token.to_string())
+        })
+        .collect()
 }